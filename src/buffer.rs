@@ -0,0 +1,31 @@
+use std::{cell::RefCell, io::{self, Write}, rc::Rc};
+
+#[derive(Debug, Clone, Default)]
+pub struct SharedBuffer {
+    data: Rc<RefCell<Vec<u8>>>
+}
+
+impl SharedBuffer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn contents(&self) -> String {
+        String::from_utf8_lossy(&self.data.borrow()).into_owned()
+    }
+
+    pub fn clear(&self) {
+        self.data.borrow_mut().clear();
+    }
+}
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.data.borrow_mut().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,271 @@
+use std::io::{stdin, stdout, BufRead, Write};
+
+use stacc::tokens::TokenType;
+use stacc::{compile, Hook, Interpreter, Interrupt, Object, Step};
+
+use crate::repl::format_stack;
+
+const HELP: &str = "\
+commands:
+  s, step                 execute the next token, stepping into calls
+  n, next                 execute the next token, stepping over calls
+  f, finish               run until the current call returns
+  c, continue             run until the next breakpoint
+  b, break label <name>   break when reaching a label
+  b, break line <number>  break when reaching a line
+  b, break fn <name>      break when entering a function
+  bl, breakpoints         list breakpoints
+  d, delete <index>       delete a breakpoint
+  p, stacks               print both stacks
+  push <st|nd> <values>   push literal values on a stack
+  pop <st|nd>             pop the top of a stack
+  clear <st|nd>           empty a stack
+  q, quit                 stop the program
+  h, help                 show this message";
+
+enum Breakpoint {
+    Label(String),
+    Line(usize),
+    Function(String)
+}
+
+impl std::fmt::Display for Breakpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Breakpoint::Label(name)    => write!(f, "label [{}]", name),
+            Breakpoint::Line(line)     => write!(f, "line {}", line),
+            Breakpoint::Function(name) => write!(f, "function {}", name),
+        }
+    }
+}
+
+enum Mode {
+    Continue,
+    StepInto,
+    StepOver(usize),
+    Finish(usize)
+}
+
+pub struct Debugger {
+    breakpoints: Vec<Breakpoint>,
+    mode: Mode,
+    last_step: Option<(usize, usize, usize)>
+}
+
+fn parse_values(source: &str) -> Result<Vec<Object>, String> {
+    let code = compile(source, "<debugger>").map_err(|errors| {
+        errors.into_iter().map(|e| e.message).collect::<Vec<String>>().join(", ")
+    })?;
+
+    let mut values = Vec::new();
+    for token in code.tokens {
+        match token.type_ {
+            TokenType::Int(x)    => values.push(Object::Int(x)),
+            TokenType::BigInt(x) => values.push(Object::BigInt(x)),
+            TokenType::Float(x)  => values.push(Object::Float(x)),
+            TokenType::String(x) => values.push(Object::String(x)),
+            TokenType::Code(x)   => values.push(Object::Code(x)),
+            TokenType::EOF => break,
+            _ => return Err(format!("\"{}\" is not a value", token.lexeme)),
+        }
+    }
+
+    Ok(values)
+}
+
+impl Debugger {
+    pub fn new() -> Self {
+        Debugger { breakpoints: Vec::new(), mode: Mode::StepInto, last_step: None }
+    }
+
+    fn hit_breakpoint(&self, interpreter: &Interpreter, step: &Step) -> bool {
+        self.breakpoints.iter().any(|breakpoint| {
+            match breakpoint {
+                Breakpoint::Label(name) => {
+                    step.code.labels.get(name.as_str()) == Some(&step.index)
+                }
+                Breakpoint::Line(line) => {
+                    step.token.line + 1 == *line && !Self::at_end(step) && !self.same_line(step)
+                }
+                Breakpoint::Function(name) => {
+                    step.index == 0 && interpreter.frames().last().is_some_and(|f| f.as_ref() == name)
+                }
+            }
+        })
+    }
+
+    fn at_end(step: &Step) -> bool {
+        matches!(step.token.type_, TokenType::EOF)
+    }
+
+    /// Whether the step continues the line of the previous one, instead of
+    /// reaching it again through a jump backwards or a call.
+    fn same_line(&self, step: &Step) -> bool {
+        self.last_step.is_some_and(|(line, depth, index)| {
+            line == step.token.line && depth == step.depth && index < step.index
+        })
+    }
+
+    fn should_stop(&self, interpreter: &Interpreter, step: &Step) -> bool {
+        let stop = !Self::at_end(step) && match self.mode {
+            Mode::Continue        => false,
+            Mode::StepInto        => true,
+            Mode::StepOver(depth) => step.depth <= depth,
+            Mode::Finish(depth)   => step.depth < depth,
+        };
+
+        stop || self.hit_breakpoint(interpreter, step)
+    }
+
+    fn show_location(&self, interpreter: &Interpreter, step: &Step) {
+        let token = step.token;
+        let function = interpreter.frames().last().map(|f| f.to_string()).unwrap_or(String::from("<main>"));
+
+        if Self::at_end(step) {
+            eprintln!("-> {} (line {}) in {}, depth {}: end of code", token.source_name, token.line + 1, function, step.depth);
+            return;
+        }
+
+        eprintln!(
+            "-> {} (line {}, pos {}) in {}, depth {}: {}",
+            token.source_name, token.line + 1, token.pos, function, step.depth, token.lexeme
+        );
+
+        if let Some(line) = token.source.lines().nth(token.line) {
+            let prefix = format!("{} | ", token.line + 1);
+            eprintln!("{}{}", prefix, line.trim_end());
+            eprintln!(
+                "{}{}", " ".repeat(prefix.len() + token.pos),
+                "^".repeat(token.end.saturating_sub(token.pos).max(1))
+            );
+        }
+    }
+
+    fn edit_stack(interpreter: &mut Interpreter, command: &str, target: &str, rest: &str) {
+        let stack = match target {
+            "st" => interpreter.primary_mut(),
+            "nd" => interpreter.secondary_mut(),
+            _ => {
+                eprintln!("expecting \"st\" or \"nd\" as stack name");
+                return;
+            }
+        };
+
+        match command {
+            "push" => {
+                match parse_values(rest) {
+                    Ok(mut values) => stack.append(&mut values),
+                    Err(e) => eprintln!("{}", e),
+                }
+            }
+            "pop" => {
+                if let Some(popped) = stack.pop() {
+                    eprintln!("{}", popped.repr());
+                } else {
+                    eprintln!("stack is empty");
+                }
+            }
+            _ => stack.clear(),
+        }
+    }
+
+    fn add_breakpoint(&mut self, kind: &str, arg: &str) {
+        let breakpoint = match kind {
+            "label" if !arg.is_empty() => Breakpoint::Label(arg.to_string()),
+            "fn"    if !arg.is_empty() => Breakpoint::Function(arg.to_string()),
+            "line" => {
+                if let Ok(line) = arg.parse() {
+                    Breakpoint::Line(line)
+                } else {
+                    eprintln!("expecting line number");
+                    return;
+                }
+            }
+            _ => {
+                eprintln!("usage: break <label|line|fn> <target>");
+                return;
+            }
+        };
+
+        eprintln!("breakpoint {}: {}", self.breakpoints.len(), breakpoint);
+        self.breakpoints.push(breakpoint);
+    }
+
+    fn prompt(&mut self, interpreter: &mut Interpreter, step: &Step) -> Result<(), Interrupt> {
+        self.show_location(interpreter, step);
+
+        let mut line = String::new();
+        loop {
+            eprint!("(debug) ");
+            let _ = stdout().flush();
+
+            line.clear();
+            if stdin().lock().read_line(&mut line).unwrap_or(0) == 0 {
+                self.mode = Mode::Continue;
+                return Ok(());
+            }
+
+            let mut words = line.trim().splitn(3, char::is_whitespace);
+            let command = words.next().unwrap_or("");
+            let target = words.next().unwrap_or("");
+            let rest = words.next().unwrap_or("").trim();
+
+            match command {
+                "" => (),
+                "s" | "step" => {
+                    self.mode = Mode::StepInto;
+                    return Ok(());
+                }
+                "n" | "next" => {
+                    self.mode = Mode::StepOver(step.depth);
+                    return Ok(());
+                }
+                "f" | "finish" => {
+                    self.mode = Mode::Finish(step.depth);
+                    return Ok(());
+                }
+                "c" | "continue" => {
+                    self.mode = Mode::Continue;
+                    return Ok(());
+                }
+                "b" | "break" => self.add_breakpoint(target, rest),
+                "bl" | "breakpoints" => {
+                    for (i, breakpoint) in self.breakpoints.iter().enumerate() {
+                        eprintln!("{}: {}", i, breakpoint);
+                    }
+                }
+                "d" | "delete" => {
+                    match target.parse::<usize>() {
+                        Ok(i) if i < self.breakpoints.len() => {
+                            self.breakpoints.remove(i);
+                        }
+                        _ => eprintln!("no such breakpoint"),
+                    }
+                }
+                "p" | "stacks" => {
+                    eprintln!(" FIRST STACK: {}", format_stack(interpreter.primary()));
+                    eprintln!("SECOND STACK: {}", format_stack(interpreter.secondary()));
+                }
+                "push" | "pop" | "clear" => Self::edit_stack(interpreter, command, target, rest),
+                "q" | "quit" => return Err(Interrupt::Exit(0)),
+                "h" | "help" => eprintln!("{}", HELP),
+                _ => eprintln!("unknown command \"{}\", type \"help\" for a list of commands", command),
+            }
+        }
+    }
+}
+
+impl Hook for Debugger {
+    fn before_token(&mut self, interpreter: &mut Interpreter, step: &Step) -> Result<(), Interrupt> {
+        let result = {
+            if self.should_stop(interpreter, step) {
+                self.prompt(interpreter, step)
+            } else {
+                Ok(())
+            }
+        };
+
+        self.last_step = Some((step.token.line, step.depth, step.index));
+        result
+    }
+}
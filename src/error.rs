@@ -0,0 +1,157 @@
+use std::{cmp::max, fmt, rc::Rc};
+
+use crate::tokens::Token;
+use crate::utils::call_name;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    Syntax,
+    StackUnderflow,
+    Type,
+    UnknownLabel,
+    LabelConflict,
+    UndefinedFunction,
+    ReservedName,
+    OutOfRange,
+    DivisionByZero,
+    Overflow,
+    MissingKey,
+    UnknownStack,
+    StackConflict,
+    Io,
+    Native,
+    StepLimit,
+    DepthLimit,
+    StackLimit,
+    StringLimit,
+    IntLimit,
+    ItemLimit,
+    CodeLimit
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    pub line: usize,
+    pub pos: usize,
+    pub end: usize
+}
+
+impl Span {
+    pub fn new(line: usize, pos: usize, end: usize) -> Self {
+        Span { line, pos, end }
+    }
+
+    pub fn of(token: &Token) -> Self {
+        Span::new(token.line, token.pos, token.end)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Frame {
+    pub function: Rc<str>,
+    pub source_name: Rc<str>,
+    pub span: Span
+}
+
+#[derive(Debug, Clone)]
+pub struct StaccError {
+    pub kind: ErrorKind,
+    pub message: String,
+    pub span: Span,
+    pub source_name: Rc<str>,
+    pub source: Rc<str>,
+    pub call_chain: Vec<Frame>
+}
+
+impl ErrorKind {
+    pub fn is_limit(&self) -> bool {
+        matches!(
+            self,
+            ErrorKind::StepLimit | ErrorKind::DepthLimit | ErrorKind::StackLimit |
+            ErrorKind::StringLimit | ErrorKind::IntLimit | ErrorKind::ItemLimit |
+            ErrorKind::CodeLimit
+        )
+    }
+}
+
+impl StaccError {
+    pub fn new(kind: ErrorKind, message: &str, span: Span, source_name: Rc<str>, source: Rc<str>) -> Self {
+        StaccError {
+            kind, message: message.to_string(), span,
+            source_name, source, call_chain: Vec::new()
+        }
+    }
+
+    pub fn at_token(kind: ErrorKind, message: &str, token: &Token) -> Self {
+        StaccError::new(
+            kind, message, Span::of(token),
+            Rc::clone(&token.source_name), Rc::clone(&token.source)
+        )
+    }
+
+    pub fn native(kind: ErrorKind, message: &str) -> Self {
+        StaccError::new(kind, message, Span::new(0, 0, 0), Rc::from("<native>"), Rc::from(""))
+    }
+
+    pub fn located_at(mut self, token: &Token) -> Self {
+        self.span = Span::of(token);
+        self.source_name = Rc::clone(&token.source_name);
+        self.source = Rc::clone(&token.source);
+        self
+    }
+
+    pub fn called_from(&mut self, token: &Token) {
+        self.call_chain.push(Frame {
+            function: call_name(token), source_name: Rc::clone(&token.source_name), span: Span::of(token)
+        });
+    }
+
+    pub fn is_runtime(&self) -> bool {
+        self.kind != ErrorKind::Syntax
+    }
+}
+
+impl fmt::Display for StaccError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let type_ = if self.is_runtime() { "runtime error" } else { "error" };
+        let line = self.span.line;
+        let pos = self.span.pos;
+        let len = self.span.end.saturating_sub(pos);
+
+        let lines = self.source.lines().collect::<Vec<&str>>();
+        let iter_range = {
+            if lines.len() < 5 {
+                0..lines.len()
+            } else if line <= 2 {
+                0..5
+            } else if line >= lines.len() - 3 {
+                (lines.len() - 5)..lines.len()
+            } else {
+                (line - 2)..(line + 3)
+            }
+        };
+
+        let linelen = max((iter_range.end as f64).log10().ceil() as usize, 1);
+
+        write!(f, "{} ({}, line {}, pos {}): {}", type_, self.source_name, line + 1, pos, self.message)?;
+
+        for l in iter_range {
+            write!(f, "\n{:linelen$} | {}", l + 1, lines[l].trim_end())?;
+
+            if l == line {
+                write!(f, "\n{} | {}{}", " ".repeat(linelen), " ".repeat(pos), "^".repeat(len))?;
+            }
+        }
+
+        for frame in &self.call_chain {
+            write!(
+                f, "\n  called from {} ({}, line {}, pos {})",
+                frame.function, frame.source_name, frame.span.line + 1, frame.span.pos
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+impl std::error::Error for StaccError {}
@@ -0,0 +1,1630 @@
+use std::{collections::{BTreeMap, HashMap}, io::{self, stdin, stdout, BufRead, Write}, rc::Rc};
+
+use num_bigint::{BigInt, BigUint, Sign};
+use num_traits::{FromPrimitive, Pow, Signed, ToPrimitive, Zero};
+
+use crate::error::{ErrorKind, StaccError};
+use crate::scanner::Scanner;
+use crate::tokens::{keyword, Token, TokenType};
+use crate::{token_runtime_error, utils, Code, Interrupt, Key, Object};
+
+pub type NativeFunction = dyn Fn(&mut Interpreter) -> Result<(), StaccError>;
+
+#[derive(Clone)]
+enum Function {
+    Code(Code),
+    Native(Rc<NativeFunction>)
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Limits {
+    pub max_steps: Option<usize>,
+    pub max_depth: Option<usize>,
+    pub max_stack: Option<usize>,
+    pub max_string: Option<usize>,
+    pub max_int_bits: Option<usize>,
+    pub max_items: Option<usize>,
+    pub max_code: Option<usize>
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            max_steps: None,
+            max_depth: None,
+            max_stack: None,
+            max_string: None,
+            max_int_bits: Some(1 << 20),
+            max_items: Some(1 << 20),
+            max_code: Some(1 << 20)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Overflow {
+    #[default]
+    Promote,
+    Wrap,
+    Error,
+    Saturate
+}
+
+impl Overflow {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "promote"  => Some(Overflow::Promote),
+            "wrap"     => Some(Overflow::Wrap),
+            "error"    => Some(Overflow::Error),
+            "saturate" => Some(Overflow::Saturate),
+            _ => None
+        }
+    }
+}
+
+pub struct Step<'a> {
+    pub token: &'a Token,
+    pub code: &'a Code,
+    pub index: usize,
+    pub depth: usize
+}
+
+pub trait Hook {
+    fn before_token(&mut self, interpreter: &mut Interpreter, step: &Step) -> Result<(), Interrupt>;
+}
+
+pub struct Interpreter {
+    st_stack: Vec<Object>,
+    nd_stack: Vec<Object>,
+    st_name: Rc<str>,
+    nd_name: Rc<str>,
+    stacks: HashMap<Rc<str>, Vec<Object>>,
+    functions: HashMap<Rc<str>, Function>,
+    output: Box<dyn Write>,
+    input: Option<Box<dyn BufRead>>,
+    limits: Limits,
+    overflow: Overflow,
+    steps: usize,
+    frames: Vec<Rc<str>>,
+    hook: Option<Box<dyn Hook>>,
+}
+
+macro_rules! numeric_op {
+    ($slf: ident, $tok: ident, $a: expr, $b: expr, $op: tt, $on_int_op: ident) => {
+        match ($a, $b) {
+            (Object::Int(x), Object::Int(y)) => {
+                match x.$on_int_op(*y) {
+                    Some(result) => Some(Object::Int(result)),
+                    None => Some($slf.fit_integer($tok, BigInt::from(*x) $op BigInt::from(*y))?),
+                }
+            }
+            (Object::Int(x),    Object::BigInt(y)) => Some($slf.fit_integer($tok, BigInt::from(*x) $op y.as_ref())?),
+            (Object::BigInt(x), Object::Int(y))    => Some($slf.fit_integer($tok, x.as_ref() $op BigInt::from(*y))?),
+            (Object::BigInt(x), Object::BigInt(y)) => Some($slf.fit_integer($tok, x.as_ref() $op y.as_ref())?),
+            (Object::Int(x),    Object::Float(y))  => Some(Object::Float(*x as f64 $op y)),
+            (Object::Float(x),  Object::Int(y))    => Some(Object::Float(x $op *y as f64)),
+            (Object::BigInt(x), Object::Float(y))  => Some(Object::Float(utils::big_to_f64(x) $op y)),
+            (Object::Float(x),  Object::BigInt(y)) => Some(Object::Float(x $op utils::big_to_f64(y))),
+            (Object::Float(x),  Object::Float(y))  => Some(Object::Float(x $op y)),
+            _ => None
+        }
+    };
+}
+
+macro_rules! simple_binary {
+    ($slf: ident, $tok: ident, $op: tt, $on_int_op: ident) => {
+        {
+            let b = $slf.checked_pop($tok)?;
+            let a = $slf.checked_pop($tok)?;
+
+            if let Some(result) = numeric_op!($slf, $tok, &a, &b, $op, $on_int_op) {
+                $slf.checked_push($tok, result)?;
+            } else {
+                token_runtime_error!(
+                    $tok,
+                    ErrorKind::Type,
+                    format!("Cannot perform this operation on type {:?}", if a.is_number() { b } else { a }).as_ref()
+                );
+            }
+        }
+    };
+}
+
+macro_rules! bitwise_binary {
+    ($slf: ident, $tok: ident, $op: tt) => {
+        {
+            let b = $slf.checked_pop($tok)?;
+            let a = $slf.checked_pop($tok)?;
+
+            let result = {
+                match (&a, &b) {
+                    (Object::Int(x),    Object::Int(y))    => Object::Int(x $op y),
+                    (Object::Int(x),    Object::BigInt(y)) => $slf.fit_integer($tok, BigInt::from(*x) $op y.as_ref())?,
+                    (Object::BigInt(x), Object::Int(y))    => $slf.fit_integer($tok, x.as_ref() $op BigInt::from(*y))?,
+                    (Object::BigInt(x), Object::BigInt(y)) => $slf.fit_integer($tok, x.as_ref() $op y.as_ref())?,
+                    _ => {
+                        token_runtime_error!(
+                            $tok,
+                            ErrorKind::Type,
+                            format!(
+                                "Cannot perform this operation on type {:?}",
+                                if let Object::Int(_) | Object::BigInt(_) = a { b } else { a }
+                            ).as_ref()
+                        );
+                    }
+                }
+            };
+
+            $slf.checked_push($tok, result)?;
+        }
+    };
+}
+
+macro_rules! cmp_binary {
+    ($slf: ident, $tok: ident, $op: tt) => {
+        {
+            let b = $slf.checked_pop($tok)?;
+            let a = $slf.checked_pop($tok)?;
+                        
+            let result = {
+                match a {
+                    Object::Int(x) => {
+                        match b {
+                            Object::Int(y)    => x $op y,
+                            Object::BigInt(y) => BigInt::from(x) $op *y,
+                            Object::Float(y)  => (x as f64) $op y,
+                            _ => {
+                                token_runtime_error!(
+                                    $tok,
+                                    ErrorKind::Type,
+                                    format!("Cannot perform this operation on type {:?}", b).as_ref()
+                                );
+                            }
+                        }
+                    }
+                    Object::BigInt(x) => {
+                        match b {
+                            Object::Int(y)    => *x $op BigInt::from(y),
+                            Object::BigInt(y) => x $op y,
+                            Object::Float(y)  => utils::big_to_f64(&x) $op y,
+                            _ => {
+                                token_runtime_error!(
+                                    $tok,
+                                    ErrorKind::Type,
+                                    format!("Cannot perform this operation on type {:?}", b).as_ref()
+                                );
+                            }
+                        }
+                    }
+                    Object::Float(x) => {
+                        match b {
+                            Object::Int(y)    => x $op y as f64,
+                            Object::BigInt(y) => x $op utils::big_to_f64(&y),
+                            Object::Float(y)  => x $op y,
+                            _ => {
+                                token_runtime_error!(
+                                    $tok,
+                                    ErrorKind::Type,
+                                    format!("Cannot perform this operation on type {:?}", b).as_ref()
+                                );
+                            }
+                        }
+                    }
+                    Object::String(x) => {
+                        match b {
+                            Object::String(y) => x $op y,
+                            _ => {
+                                token_runtime_error!(
+                                    $tok,
+                                    ErrorKind::Type,
+                                    format!("Cannot perform this operation on type {:?}", b).as_ref()
+                                );
+                            }
+                        }
+                    }
+                    Object::List(x) => {
+                        match b {
+                            Object::List(y) => x $op y,
+                            _ => {
+                                token_runtime_error!(
+                                    $tok,
+                                    ErrorKind::Type,
+                                    format!("Cannot perform this operation on type {:?}", b).as_ref()
+                                );
+                            }
+                        }
+                    }
+                    Object::Map(x) => {
+                        match b {
+                            Object::Map(y) => x $op y,
+                            _ => {
+                                token_runtime_error!(
+                                    $tok,
+                                    ErrorKind::Type,
+                                    format!("Cannot perform this operation on type {:?}", b).as_ref()
+                                );
+                            }
+                        }
+                    }
+                    _ => {
+                        token_runtime_error!(
+                            $tok,
+                            ErrorKind::Type,
+                            format!("Cannot perform this operation on type {:?}", a).as_ref()
+                        );
+                    }
+                }
+            };
+
+            $slf.checked_push($tok, Object::Int(result as i64))?;
+        }
+    };
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Interpreter {
+    pub fn new() -> Self {
+        Self {
+            st_stack: Vec::new(),
+            nd_stack: Vec::new(),
+            st_name: Rc::from("st"),
+            nd_name: Rc::from("nd"),
+            stacks: HashMap::new(),
+            functions: HashMap::new(),
+            output: Box::new(stdout()),
+            input: None,
+            limits: Limits::default(),
+            overflow: Overflow::default(),
+            steps: 0,
+            frames: Vec::new(),
+            hook: None,
+        }
+    }
+
+    pub fn run(&mut self, code: &Code) -> Result<(), Interrupt> {
+        self.steps = 0;
+        self.frames.clear();
+        match reblessive::Stack::new().enter(|ctx| self.execute(code, ctx)).finish() {
+            Err(Interrupt::Return | Interrupt::Halt) => Ok(()),
+            result => result
+        }
+    }
+
+    pub fn push(&mut self, object: Object) {
+        self.st_stack.push(object);
+    }
+
+    pub fn pop(&mut self) -> Option<Object> {
+        self.st_stack.pop()
+    }
+
+    pub fn primary(&self) -> &[Object] {
+        &self.st_stack
+    }
+
+    pub fn secondary(&self) -> &[Object] {
+        &self.nd_stack
+    }
+
+    pub fn primary_mut(&mut self) -> &mut Vec<Object> {
+        &mut self.st_stack
+    }
+
+    pub fn secondary_mut(&mut self) -> &mut Vec<Object> {
+        &mut self.nd_stack
+    }
+
+    pub fn primary_name(&self) -> &str {
+        &self.st_name
+    }
+
+    pub fn secondary_name(&self) -> &str {
+        &self.nd_name
+    }
+
+    pub fn stack(&self, name: &str) -> Option<&[Object]> {
+        if name == self.st_name.as_ref() {
+            Some(&self.st_stack)
+        } else if name == self.nd_name.as_ref() {
+            Some(&self.nd_stack)
+        } else {
+            self.stacks.get(name).map(|stack| stack.as_slice())
+        }
+    }
+
+    pub fn frames(&self) -> &[Rc<str>] {
+        &self.frames
+    }
+
+    pub fn set_output(&mut self, output: impl Write + 'static) {
+        self.output = Box::new(output);
+    }
+
+    pub fn set_input(&mut self, input: impl BufRead + 'static) {
+        self.input = Some(Box::new(input));
+    }
+
+    pub fn set_limits(&mut self, limits: Limits) {
+        self.limits = limits;
+    }
+
+    pub fn set_overflow(&mut self, overflow: Overflow) {
+        self.overflow = overflow;
+    }
+
+    pub fn set_hook(&mut self, hook: impl Hook + 'static) {
+        self.hook = Some(Box::new(hook));
+    }
+
+    pub fn register<F>(&mut self, name: &str, function: F) -> Result<(), StaccError>
+    where
+        F: Fn(&mut Interpreter) -> Result<(), StaccError> + 'static
+    {
+        let mut chars = name.chars();
+        if !chars.next().is_some_and(utils::is_alpha) || !chars.all(utils::is_alphanumeric) {
+            return Err(StaccError::native(
+                ErrorKind::Syntax,
+                format!("Invalid function name \"{}\"", name).as_ref()
+            ));
+        }
+
+        if keyword(name).is_some() {
+            return Err(StaccError::native(
+                ErrorKind::ReservedName,
+                format!("Cannot redefine built-in operation \"{}\"", name).as_ref()
+            ));
+        }
+
+        self.functions.insert(Rc::from(name), Function::Native(Rc::new(function)));
+        Ok(())
+    }
+
+    fn read_input<T>(&mut self, read: impl FnOnce(&mut dyn BufRead) -> io::Result<T>) -> io::Result<T> {
+        match &mut self.input {
+            Some(input) => read(input.as_mut()),
+            None => read(&mut stdin().lock()),
+        }
+    }
+
+    fn check_object(&self, tok: &Token, object: &Object) -> Result<(), Interrupt> {
+        if let (Some(max), Object::String(x)) = (self.limits.max_string, object) {
+            if x.len() > max {
+                token_runtime_error!(
+                    tok,
+                    ErrorKind::StringLimit,
+                    format!("String exceeds maximum size of {} bytes", max).as_ref()
+                );
+            }
+        }
+
+        if let (Some(max), Object::BigInt(x)) = (self.limits.max_int_bits, object) {
+            if x.bits() > max as u64 {
+                token_runtime_error!(
+                    tok,
+                    ErrorKind::IntLimit,
+                    format!("Integer exceeds maximum size of {} bits", max).as_ref()
+                );
+            }
+        }
+
+        if let Some(max) = self.limits.max_items {
+            if utils::count_items(object, max) > max {
+                token_runtime_error!(
+                    tok,
+                    ErrorKind::ItemLimit,
+                    format!("Collection exceeds maximum of {} items", max).as_ref()
+                );
+            }
+        }
+
+        if let (Some(max), Object::Code(x)) = (self.limits.max_code, object) {
+            if x.tokens.len() > max {
+                token_runtime_error!(
+                    tok,
+                    ErrorKind::CodeLimit,
+                    format!("Code object exceeds maximum of {} tokens", max).as_ref()
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    fn fit_integer(&self, tok: &Token, value: BigInt) -> Result<Object, Interrupt> {
+        if i64::try_from(&value).is_ok() {
+            return Ok(Object::from(value));
+        }
+
+        match self.overflow {
+            Overflow::Promote  => Ok(Object::from(value)),
+            Overflow::Wrap     => Ok(Object::Int(utils::wrap_to_i64(&value))),
+            Overflow::Saturate => Ok(Object::Int(if value.sign() == Sign::Minus { i64::MIN } else { i64::MAX })),
+            Overflow::Error    => {
+                token_runtime_error!(
+                    tok,
+                    ErrorKind::Overflow,
+                    format!("Integer overflow (result is {})", value).as_ref()
+                );
+            }
+        }
+    }
+
+    fn check_divisor(&self, tok: &Token, message: &str) -> Result<(), Interrupt> {
+        if let [.., Object::Int(_) | Object::BigInt(_), Object::Int(0)] = self.st_stack.as_slice() {
+            token_runtime_error!(tok, ErrorKind::DivisionByZero, message);
+        }
+
+        Ok(())
+    }
+
+    fn checked_push(&mut self, tok: &Token, object: Object) -> Result<(), Interrupt> {
+        self.check_object(tok, &object)?;
+
+        if let Some(max) = self.limits.max_stack {
+            if self.st_stack.len() >= max {
+                token_runtime_error!(
+                    tok,
+                    ErrorKind::StackLimit,
+                    format!("Primary stack exceeds maximum length of {}", max).as_ref()
+                );
+            }
+        }
+
+        self.st_stack.push(object);
+        Ok(())
+    }
+
+    fn checked_push_nd(&mut self, tok: &Token, object: Object) -> Result<(), Interrupt> {
+        self.check_object(tok, &object)?;
+
+        if let Some(max) = self.limits.max_stack {
+            if self.nd_stack.len() >= max {
+                token_runtime_error!(
+                    tok,
+                    ErrorKind::StackLimit,
+                    format!("Secondary stack exceeds maximum length of {}", max).as_ref()
+                );
+            }
+        }
+
+        self.nd_stack.push(object);
+        Ok(())
+    }
+
+    fn checked_pop(&mut self, tok: &Token) -> Result<Object, Interrupt> {
+        if let Some(popped) = self.st_stack.pop() {
+            Ok(popped)
+        } else {
+            token_runtime_error!(tok, ErrorKind::StackUnderflow, "Popped empty primary stack");
+        }
+    }
+
+    fn checked_pop_int(&mut self, tok: &Token, what: &str) -> Result<i64, Interrupt> {
+        let popped = self.checked_pop(tok)?;
+        if let Object::Int(x) = popped {
+            Ok(x)
+        } else {
+            token_runtime_error!(
+                tok,
+                ErrorKind::Type,
+                format!("Expecting int as {} (got {:?})", what, popped).as_ref()
+            );
+        }
+    }
+
+    fn checked_pop_string(&mut self, tok: &Token, what: &str) -> Result<Rc<str>, Interrupt> {
+        let popped = self.checked_pop(tok)?;
+        if let Object::String(x) = popped {
+            Ok(x)
+        } else {
+            token_runtime_error!(
+                tok,
+                ErrorKind::Type,
+                format!("Expecting string as {} (got {:?})", what, popped).as_ref()
+            );
+        }
+    }
+
+    fn checked_pop_code(&mut self, tok: &Token, what: &str) -> Result<Code, Interrupt> {
+        let popped = self.checked_pop(tok)?;
+        if let Object::Code(x) = popped {
+            Ok(x)
+        } else {
+            token_runtime_error!(
+                tok,
+                ErrorKind::Type,
+                format!("Expecting code object as {} (got {:?})", what, popped).as_ref()
+            );
+        }
+    }
+
+    fn checked_key(&self, tok: &Token, object: &Object) -> Result<Key, Interrupt> {
+        if let Some(key) = Key::from_object(object) {
+            Ok(key)
+        } else {
+            token_runtime_error!(
+                tok,
+                ErrorKind::Type,
+                format!("Expecting int or string as map key (got {:?})", object).as_ref()
+            );
+        }
+    }
+
+    fn checked_pop_nd(&mut self, tok: &Token) -> Result<Object, Interrupt> {
+        if let Some(popped) = self.nd_stack.pop() {
+            Ok(popped)
+        } else {
+            token_runtime_error!(tok, ErrorKind::StackUnderflow, "Popped empty secondary stack");
+        }
+    }
+ 
+    fn require_items(&self, tok: &Token, secondary: bool, count: usize) -> Result<(), Interrupt> {
+        let (stack, name) = if secondary { (&self.nd_stack, "secondary") } else { (&self.st_stack, "primary") };
+        if stack.len() < count {
+            token_runtime_error!(
+                tok,
+                ErrorKind::StackUnderflow,
+                format!("Expecting at least {} items on the {} stack (found {})", count, name, stack.len()).as_ref()
+            );
+        }
+
+        Ok(())
+    }
+
+    fn shuffle(&mut self, tok: &Token) -> Result<(), Interrupt> {
+        let secondary = matches!(
+            tok.type_,
+            TokenType::NdSwap | TokenType::NdOver | TokenType::NdRot | TokenType::NdPick | TokenType::NdRoll
+        );
+
+        let index = {
+            if let TokenType::Pick | TokenType::Roll | TokenType::NdPick | TokenType::NdRoll = tok.type_ {
+                let index = self.checked_pop_int(tok, "stack index")?;
+                if index < 0 {
+                    token_runtime_error!(
+                        tok,
+                        ErrorKind::OutOfRange,
+                        format!("Stack index {} is out of range", index).as_ref()
+                    );
+                }
+
+                index as usize
+            } else {
+                0
+            }
+        };
+
+        let required = match tok.type_ {
+            TokenType::Drop => 1,
+            TokenType::Swap | TokenType::Over | TokenType::NdSwap | TokenType::NdOver => 2,
+            TokenType::Rot | TokenType::NdRot => 3,
+            _ => index.saturating_add(1),
+        };
+
+        self.require_items(tok, secondary, required)?;
+
+        let stack = if secondary { &mut self.nd_stack } else { &mut self.st_stack };
+        let len = stack.len();
+
+        match tok.type_ {
+            TokenType::Drop => {
+                stack.pop();
+            }
+            TokenType::Swap | TokenType::NdSwap => stack.swap(len - 1, len - 2),
+            TokenType::Rot  | TokenType::NdRot  => stack[len - 3..].rotate_left(1),
+            TokenType::Roll | TokenType::NdRoll => stack[len - 1 - index..].rotate_left(1),
+            _ => {
+                let copied = {
+                    if let TokenType::Over | TokenType::NdOver = tok.type_ {
+                        stack[len - 2].clone()
+                    } else {
+                        stack[len - 1 - index].clone()
+                    }
+                };
+
+                if secondary {
+                    self.checked_push_nd(tok, copied)?;
+                } else {
+                    self.checked_push(tok, copied)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn narrow(&self, tok: &Token, object: &Object) -> Result<i64, Interrupt> {
+        let value = match object {
+            Object::Int(x)    => return Ok(*x),
+            Object::BigInt(x) => x.as_ref().clone(),
+            _ => {
+                token_runtime_error!(
+                    tok,
+                    ErrorKind::Type,
+                    format!("Cannot perform this operation on type {:?}", object).as_ref()
+                );
+            }
+        };
+
+        // Wrap and Saturate bring the value into 64 bits, Promote can't, so it's an overflow too
+        match self.fit_integer(tok, value)? {
+            Object::Int(x) => Ok(x),
+            object => {
+                token_runtime_error!(
+                    tok,
+                    ErrorKind::Overflow,
+                    format!("Integer {} doesn't fit in 64 bits", object).as_ref()
+                );
+            }
+        }
+    }
+
+    fn shift(&mut self, tok: &Token) -> Result<(), Interrupt> {
+        let b = self.checked_pop(tok)?;
+        let a = self.checked_pop(tok)?;
+
+        let Object::Int(n) = b else {
+            token_runtime_error!(
+                tok,
+                ErrorKind::Type,
+                format!("Cannot perform this operation on type {:?}", b).as_ref()
+            );
+        };
+
+        if let TokenType::RotL | TokenType::RotR | TokenType::UShr = tok.type_ {
+            let x = self.narrow(tok, &a)?;
+            let result = match tok.type_ {
+                TokenType::RotL => x.rotate_left(n.rem_euclid(64) as u32),
+                TokenType::RotR => x.rotate_right(n.rem_euclid(64) as u32),
+                _ => {
+                    if !(0..64).contains(&n) {
+                        token_runtime_error!(
+                            tok,
+                            ErrorKind::OutOfRange,
+                            format!("Shift amount {} is out of range", n).as_ref()
+                        );
+                    }
+
+                    ((x as u64) >> n) as i64
+                }
+            };
+
+            return self.checked_push(tok, Object::Int(result));
+        }
+
+        let x = match a {
+            Object::Int(x)    => BigInt::from(x),
+            Object::BigInt(x) => x.as_ref().clone(),
+            _ => {
+                token_runtime_error!(
+                    tok,
+                    ErrorKind::Type,
+                    format!("Cannot perform this operation on type {:?}", a).as_ref()
+                );
+            }
+        };
+
+        if n < 0 {
+            token_runtime_error!(
+                tok,
+                ErrorKind::OutOfRange,
+                format!("Shift amount {} is out of range", n).as_ref()
+            );
+        }
+
+        let n = n as u64;
+        let result = if matches!(tok.type_, TokenType::Shr) {
+            let n = n.min(x.bits() + 1);
+            x >> n
+        } else if x.sign() == Sign::NoSign {
+            x
+        } else if let Overflow::Promote = self.overflow {
+            if let Some(max) = self.limits.max_int_bits {
+                if x.bits().saturating_add(n) > max as u64 {
+                    token_runtime_error!(
+                        tok,
+                        ErrorKind::IntLimit,
+                        format!("Integer exceeds maximum size of {} bits", max).as_ref()
+                    );
+                }
+            }
+
+            x << n
+        } else {
+            // past 64 bits, a longer shift doesn't change how the result is wrapped or saturated
+            x << n.min(128)
+        };
+
+        let result = self.fit_integer(tok, result)?;
+        self.checked_push(tok, result)
+    }
+
+    fn int_pow(&self, tok: &Token, base: &Object, n: &BigInt) -> Result<Object, Interrupt> {
+        if let (Object::Int(x), Some(n)) = (base, n.to_u32()) {
+            match self.overflow {
+                Overflow::Wrap     => return Ok(Object::Int(x.wrapping_pow(n))),
+                Overflow::Saturate => return Ok(Object::Int(x.saturating_pow(n))),
+                Overflow::Error    => {
+                    if let Some(result) = x.checked_pow(n) {
+                        return Ok(Object::Int(result));
+                    }
+
+                    token_runtime_error!(tok, ErrorKind::Overflow, "Integer overflow");
+                }
+                Overflow::Promote  => (),
+            }
+        }
+
+        let base = match base {
+            Object::Int(x)    => BigInt::from(*x),
+            Object::BigInt(x) => x.as_ref().clone(),
+            _ => unreachable!(),
+        };
+
+        // 0, 1 and -1 never grow, so any exponent is fine for them
+        if base.magnitude() <= &BigUint::from(1u32) {
+            let result = if base.is_zero() && !n.is_zero() {
+                0
+            } else if base.is_negative() && n.bit(0) {
+                -1
+            } else {
+                1
+            };
+
+            return Ok(Object::Int(result));
+        }
+
+        match self.overflow {
+            Overflow::Promote => {
+                // base^n has more than (bits - 1) * n bits, so reject it before computing it
+                if let Some(max) = self.limits.max_int_bits {
+                    if BigInt::from(base.bits() - 1) * n >= BigInt::from(max) {
+                        token_runtime_error!(
+                            tok,
+                            ErrorKind::IntLimit,
+                            format!("Integer exceeds maximum size of {} bits", max).as_ref()
+                        );
+                    }
+                }
+
+                Ok(Object::from(Pow::pow(base, n.magnitude())))
+            }
+            Overflow::Wrap => {
+                let modulus = BigInt::from(1) << 64;
+                Ok(Object::Int(utils::wrap_to_i64(&base.modpow(n, &modulus))))
+            }
+            _ if *n < BigInt::from(2) => self.fit_integer(tok, Pow::pow(base, n.magnitude())),
+            Overflow::Saturate => {
+                let negative = base.is_negative() && n.bit(0);
+                Ok(Object::Int(if negative { i64::MIN } else { i64::MAX }))
+            }
+            Overflow::Error => {
+                token_runtime_error!(tok, ErrorKind::Overflow, "Integer overflow");
+            }
+        }
+    }
+
+    fn math(&mut self, tok: &Token) -> Result<(), Interrupt> {
+        let result = match tok.type_ {
+            TokenType::Pi    => Object::Float(std::f64::consts::PI),
+            TokenType::Euler => Object::Float(std::f64::consts::E),
+            TokenType::Pow | TokenType::Min | TokenType::Max => {
+                let b = self.checked_pop(tok)?;
+                let a = self.checked_pop(tok)?;
+
+                if !a.is_number() || !b.is_number() {
+                    token_runtime_error!(
+                        tok,
+                        ErrorKind::Type,
+                        format!("Cannot perform this operation on type {:?}", if a.is_number() { b } else { a }).as_ref()
+                    );
+                }
+
+                match (&tok.type_, &a, &b) {
+                    (TokenType::Min, _, _) => if b < a { b } else { a },
+                    (TokenType::Max, _, _) => if b > a { b } else { a },
+                    (_, Object::Int(_) | Object::BigInt(_), Object::Int(n)) if *n >= 0 => {
+                        self.int_pow(tok, &a, &BigInt::from(*n))?
+                    }
+                    (_, Object::Int(_) | Object::BigInt(_), Object::BigInt(n)) if !n.is_negative() => {
+                        self.int_pow(tok, &a, n)?
+                    }
+                    _ => Object::Float(a.to_f64().unwrap().powf(b.to_f64().unwrap())),
+                }
+            }
+            _ => {
+                let popped = self.checked_pop(tok)?;
+                match (&tok.type_, &popped) {
+                    (TokenType::Abs, Object::Int(x)) => self.fit_integer(tok, BigInt::from(*x).abs())?,
+                    (TokenType::Abs, Object::BigInt(x)) => Object::from(x.abs()),
+                    (TokenType::Floor | TokenType::Ceil | TokenType::Round, Object::Int(_) | Object::BigInt(_)) => popped,
+                    _ => {
+                        let Some(x) = popped.to_f64() else {
+                            token_runtime_error!(
+                                tok,
+                                ErrorKind::Type,
+                                format!("Cannot perform this operation on type {:?}", popped).as_ref()
+                            );
+                        };
+
+                        Object::Float(match tok.type_ {
+                            TokenType::Sqrt  => x.sqrt(),
+                            TokenType::Exp   => x.exp(),
+                            TokenType::Log   => x.ln(),
+                            TokenType::Sin   => x.sin(),
+                            TokenType::Cos   => x.cos(),
+                            TokenType::Tan   => x.tan(),
+                            TokenType::Floor => x.floor(),
+                            TokenType::Ceil  => x.ceil(),
+                            TokenType::Round => x.round(),
+                            _ => x.abs(),
+                        })
+                    }
+                }
+            }
+        };
+
+        self.checked_push(tok, result)
+    }
+
+    fn named_stack_mut(&mut self, tok: &Token, name: &str) -> Result<&mut Vec<Object>, Interrupt> {
+        if name == self.st_name.as_ref() {
+            Ok(&mut self.st_stack)
+        } else if name == self.nd_name.as_ref() {
+            Ok(&mut self.nd_stack)
+        } else if let Some(stack) = self.stacks.get_mut(name) {
+            Ok(stack)
+        } else {
+            token_runtime_error!(
+                tok,
+                ErrorKind::UnknownStack,
+                format!("Unknown stack \"{}\"", name).as_ref()
+            );
+        }
+    }
+
+    fn select_stack(&mut self, tok: &Token, name: Rc<str>, secondary: bool) -> Result<(), Interrupt> {
+        let (current, other) = if secondary { (&self.nd_name, &self.st_name) } else { (&self.st_name, &self.nd_name) };
+        if name == *current {
+            return Ok(());
+        }
+
+        if name == *other {
+            token_runtime_error!(
+                tok,
+                ErrorKind::StackConflict,
+                format!(
+                    "Stack \"{}\" is already selected as {} stack", name,
+                    if secondary { "primary" } else { "secondary" }
+                ).as_ref()
+            );
+        }
+
+        let Some(selected) = self.stacks.remove(&name) else {
+            token_runtime_error!(
+                tok,
+                ErrorKind::UnknownStack,
+                format!("Unknown stack \"{}\"", name).as_ref()
+            );
+        };
+
+        let (stack, stack_name) = {
+            if secondary {
+                (&mut self.nd_stack, &mut self.nd_name)
+            } else {
+                (&mut self.st_stack, &mut self.st_name)
+            }
+        };
+
+        let previous = std::mem::replace(stack, selected);
+        let previous_name = std::mem::replace(stack_name, name);
+        self.stacks.insert(previous_name, previous);
+        Ok(())
+    }
+
+    async fn call(&mut self, code: &Code, tok: &Token, ctx: &mut reblessive::Stk) -> Result<(), Interrupt> {
+        if let Some(max) = self.limits.max_depth {
+            if self.frames.len() >= max {
+                token_runtime_error!(
+                    tok,
+                    ErrorKind::DepthLimit,
+                    format!("Call depth exceeds maximum of {}", max).as_ref()
+                );
+            }
+        }
+
+        self.frames.push(utils::call_name(tok));
+        let result = ctx.run(|ctx| self.execute(code, ctx)).await;
+        self.frames.pop();
+
+        match result {
+            Err(Interrupt::Return) if matches!(tok.type_, TokenType::Identifier) => Ok(()),
+            result => result.map_err(|e| e.called_from(tok))
+        }
+    }
+
+    pub(crate) async fn execute(&mut self, code: &Code, ctx: &mut reblessive::Stk) -> Result<(), Interrupt> {
+        let mut i = 0usize;
+        while i < code.tokens.len() {
+            let curr = &code.tokens[i];
+
+            self.steps += 1;
+            if let Some(max) = self.limits.max_steps {
+                if self.steps > max {
+                    token_runtime_error!(
+                        curr,
+                        ErrorKind::StepLimit,
+                        format!("Execution exceeds maximum of {} steps", max).as_ref()
+                    );
+                }
+            }
+
+            if let Some(mut hook) = self.hook.take() {
+                let step = Step { token: curr, code, index: i, depth: self.frames.len() };
+                let result = hook.before_token(self, &step);
+                self.hook = Some(hook);
+                result?;
+            }
+
+            match &curr.type_ {
+                TokenType::String(value) => self.checked_push(curr, Object::String(Rc::clone(value)))?,
+                TokenType::Int(value)        => self.checked_push(curr, Object::Int(*value))?,
+                TokenType::BigInt(value)     => self.checked_push(curr, Object::BigInt(Rc::clone(value)))?,
+                TokenType::Float(value)      => self.checked_push(curr, Object::Float(*value))?,
+                TokenType::Code(code)       => self.checked_push(curr, Object::Code(code.clone()))?,
+                TokenType::EOF => break,
+
+                TokenType::At => {
+                    self.checked_pop_nd(curr)?;
+                }
+                TokenType::Hash => {
+                    let tmp = self.st_stack.clone();
+                    self.st_stack = self.nd_stack.clone();
+                    self.nd_stack = tmp;
+                    std::mem::swap(&mut self.st_name, &mut self.nd_name);
+                }
+                TokenType::Comma => {
+                    let popped = self.checked_pop(curr)?;
+                    self.checked_push_nd(curr, popped)?;
+                }
+                TokenType::Semicolon => {
+                    let popped = self.checked_pop_nd(curr)?;
+                    self.checked_push(curr, popped)?;
+                }
+                TokenType::Dot => {
+                    if let Some(peeked) = self.st_stack.last() {
+                        let peeked = peeked.clone();
+                        self.checked_push(curr, peeked)?;
+                    } else {
+                        token_runtime_error!(curr, ErrorKind::StackUnderflow, "Peeked empty primary stack");
+                    }
+                }
+                TokenType::Print => {
+                    let popped = self.checked_pop(curr)?;
+                    if let Err(e) = writeln!(self.output, "{}", popped) {
+                        token_runtime_error!(curr, ErrorKind::Io, format!("Could not write to output: {}", e).as_ref());
+                    }
+                }
+                TokenType::ReadLine => {
+                    let mut line = String::new();
+                    match self.read_input(|input| input.read_line(&mut line)) {
+                        Ok(0) => self.checked_push(curr, Object::Int(0))?,
+                        Ok(_) => {
+                            let trimmed = line.strip_suffix('\n').unwrap_or(&line);
+                            let trimmed = trimmed.strip_suffix('\r').unwrap_or(trimmed);
+                            self.checked_push(curr, Object::String(trimmed.into()))?;
+                        }
+                        Err(e) => {
+                            token_runtime_error!(curr, ErrorKind::Io, format!("Could not read from input: {}", e).as_ref());
+                        }
+                    }
+                }
+                TokenType::ReadAll => {
+                    let mut contents = String::new();
+                    match self.read_input(|input| input.read_to_string(&mut contents)) {
+                        Ok(0) => self.checked_push(curr, Object::Int(0))?,
+                        Ok(_) => self.checked_push(curr, Object::String(contents.into()))?,
+                        Err(e) => {
+                            token_runtime_error!(curr, ErrorKind::Io, format!("Could not read from input: {}", e).as_ref());
+                        }
+                    }
+                }
+                TokenType::ReadChar => {
+                    match self.read_input(utils::read_char) {
+                        Ok(Some(c)) => self.checked_push(curr, Object::String(c.to_string().into()))?,
+                        Ok(None)    => self.checked_push(curr, Object::Int(0))?,
+                        Err(e) => {
+                            token_runtime_error!(curr, ErrorKind::Io, format!("Could not read from input: {}", e).as_ref());
+                        }
+                    }
+                }
+
+                TokenType::Overflow => {
+                    let name = self.checked_pop_string(curr, "overflow policy")?;
+                    if let Some(overflow) = Overflow::from_name(&name) {
+                        self.overflow = overflow;
+                    } else {
+                        token_runtime_error!(
+                            curr,
+                            ErrorKind::OutOfRange,
+                            format!("Unknown overflow policy \"{}\"", name).as_ref()
+                        );
+                    }
+                }
+                TokenType::Return => return Err(Interrupt::Return),
+                TokenType::Halt => return Err(Interrupt::Halt),
+                TokenType::Exit => {
+                    let status = self.checked_pop_int(curr, "exit status")?;
+                    if let Ok(status) = i32::try_from(status) {
+                        return Err(Interrupt::Exit(status));
+                    } else {
+                        token_runtime_error!(
+                            curr,
+                            ErrorKind::OutOfRange,
+                            format!("Exit status {} is out of range", status).as_ref()
+                        );
+                    }
+                }
+
+                TokenType::Bang => {
+                    let popped = self.checked_pop(curr)?;
+                    self.checked_push(curr, Object::Int(popped.is_truthy() as i64))?;
+                }
+                TokenType::Tilde => {
+                    let popped = self.checked_pop(curr)?;
+                    match popped {
+                        Object::Int(x)    => self.checked_push(curr, Object::Int(!x))?,
+                        Object::BigInt(x) => {
+                            let inverted = self.fit_integer(curr, !x.as_ref())?;
+                            self.checked_push(curr, inverted)?;
+                        }
+                        Object::Float(x)  => {
+                            let truncated = {
+                                if let Some(x) = BigInt::from_f64(x) {
+                                    self.fit_integer(curr, x)?
+                                } else {
+                                    Object::Int(x as i64)
+                                }
+                            };
+
+                            self.checked_push(curr, truncated)?;
+                        }
+                        Object::String(x) => {
+                            let source = x.to_string();
+                            let mut scanner = Scanner::new(&source, "<string>");
+                            scanner.scan_tokens();
+
+                            if let Some(error) = scanner.errors.into_iter().next() {
+                                return Err(Interrupt::Error(Box::new(error)));
+                            }
+
+                            self.checked_push(curr, Object::Code(Code::new(scanner.tokens, scanner.labels)))?;
+                        }
+                        _ => {
+                            token_runtime_error!(
+                                curr,
+                                ErrorKind::Type,
+                                format!("Cannot perform this operation on type {:?}", popped).as_ref()
+                            );
+                        }
+                    }
+                }
+
+                TokenType::Plus => {
+                    let b = self.checked_pop(curr)?;
+                    let a = self.checked_pop(curr)?;
+
+                    if let Some(result) = numeric_op!(self, curr, &a, &b, +, checked_add) {
+                        self.checked_push(curr, result)?;
+                    } else {
+                        match &a {
+                            Object::Int(_) | Object::BigInt(_) | Object::Float(_) => {
+                                match b {
+                                    Object::String(y) => self.checked_push(curr, Object::String(format!("{}{}", a, y).into()))?,
+                                    _ => {
+                                        token_runtime_error!(
+                                            curr,
+                                            ErrorKind::Type,
+                                            format!("Cannot perform this operation on types {:?} and {:?}", a, b).as_ref()
+                                        );
+                                    }
+                                }
+                            }
+                            Object::String(x) => {
+                                match b {
+                                    Object::Int(_) | Object::BigInt(_) | Object::Float(_) => {
+                                        self.checked_push(curr, Object::String(format!("{}{}", x, b).into()))?
+                                    }
+                                    Object::String(y) => self.checked_push(curr, Object::String(format!("{}{}", x, y).into()))?,
+                                    _ => {
+                                        token_runtime_error!(
+                                            curr,
+                                            ErrorKind::Type,
+                                            format!("Cannot perform this operation on types {:?} and {:?}", a, b).as_ref()
+                                        );
+                                    }
+                                }
+                            }
+                            Object::Code(x) => {
+                                match b {
+                                    Object::Code(mut y) => {
+                                        let mut result = x.clone();
+                                        result.tokens.pop().expect("Malformed code"); // pops EOF
+                                        result.tokens.append(&mut y.tokens);
+                                        y.labels = y.labels.into_iter().map(|(k, v)| (k, v + x.tokens.len())).collect();
+                                    
+                                        for (label, index) in y.labels {
+                                            if x.labels.contains_key(&label) {
+                                                token_runtime_error!(
+                                                    curr,
+                                                    ErrorKind::LabelConflict,
+                                                    format!("Label \"{}\" conflicts between concatenated code objects", label).as_ref()
+                                                );
+                                            }
+
+                                            result.labels.insert(label, index + x.tokens.len());
+                                        }
+
+                                        self.checked_push(curr, Object::Code(result))?;
+                                    }
+                                    _ => {
+                                        token_runtime_error!(
+                                            curr,
+                                            ErrorKind::Type,
+                                            format!("Cannot perform this operation on types {:?} and {:?}", a, b).as_ref()
+                                        );
+                                    }
+                                }
+                            }
+                            Object::List(x) => {
+                                match b {
+                                    Object::List(y) => {
+                                        let mut result = x.as_ref().clone();
+                                        result.extend(y.iter().cloned());
+                                        self.checked_push(curr, Object::List(Rc::new(result)))?;
+                                    }
+                                    _ => {
+                                        token_runtime_error!(
+                                            curr,
+                                            ErrorKind::Type,
+                                            format!("Cannot perform this operation on types {:?} and {:?}", a, b).as_ref()
+                                        );
+                                    }
+                                }
+                            }
+                            _ => {
+                                token_runtime_error!(
+                                    curr,
+                                    ErrorKind::Type,
+                                    format!("Cannot perform this operation on types {:?} and {:?}", a, b).as_ref()
+                                );
+                            }
+                        }
+                    }
+                }
+
+                TokenType::Swap | TokenType::Over | TokenType::Rot | TokenType::Drop |
+                TokenType::Pick | TokenType::Roll | TokenType::NdSwap | TokenType::NdOver |
+                TokenType::NdRot | TokenType::NdPick | TokenType::NdRoll => self.shuffle(curr)?,
+                TokenType::Depth => self.checked_push(curr, Object::Int(self.st_stack.len() as i64))?,
+                TokenType::NdPeek | TokenType::NdDup => {
+                    if let Some(peeked) = self.nd_stack.last() {
+                        let peeked = peeked.clone();
+                        if let TokenType::NdPeek = curr.type_ {
+                            self.checked_push(curr, peeked)?;
+                        } else {
+                            self.checked_push_nd(curr, peeked)?;
+                        }
+                    } else {
+                        token_runtime_error!(curr, ErrorKind::StackUnderflow, "Peeked empty secondary stack");
+                    }
+                }
+                TokenType::NdDepth => self.checked_push(curr, Object::Int(self.nd_stack.len() as i64))?,
+                TokenType::NdClear => self.nd_stack.clear(),
+
+                TokenType::NewStack => {
+                    let name = self.checked_pop_string(curr, "stack name")?;
+                    if self.stack(&name).is_some() {
+                        token_runtime_error!(
+                            curr,
+                            ErrorKind::StackConflict,
+                            format!("Stack \"{}\" already exists", name).as_ref()
+                        );
+                    }
+
+                    self.stacks.insert(name, Vec::new());
+                }
+                TokenType::Select | TokenType::NdSelect => {
+                    let name = self.checked_pop_string(curr, "stack name")?;
+                    self.select_stack(curr, name, matches!(curr.type_, TokenType::NdSelect))?;
+                }
+                TokenType::Move => {
+                    let destination = self.checked_pop_string(curr, "destination stack name")?;
+                    let source = self.checked_pop_string(curr, "source stack name")?;
+                    let length = self.named_stack_mut(curr, &destination)?.len();
+                    if let Some(max) = self.limits.max_stack {
+                        if source != destination && length >= max {
+                            token_runtime_error!(
+                                curr,
+                                ErrorKind::StackLimit,
+                                format!("Stack \"{}\" exceeds maximum length of {}", destination, max).as_ref()
+                            );
+                        }
+                    }
+
+                    let Some(moved) = self.named_stack_mut(curr, &source)?.pop() else {
+                        token_runtime_error!(
+                            curr,
+                            ErrorKind::StackUnderflow,
+                            format!("Popped empty stack \"{}\"", source).as_ref()
+                        );
+                    };
+
+                    let stack = self.named_stack_mut(curr, &destination)?;
+                    stack.push(moved);
+                }
+
+                TokenType::Pack => {
+                    let count = self.checked_pop_int(curr, "item count")?;
+                    if count < 0 {
+                        token_runtime_error!(
+                            curr,
+                            ErrorKind::OutOfRange,
+                            format!("Cannot pack {} items", count).as_ref()
+                        );
+                    }
+
+                    if count as usize > self.st_stack.len() {
+                        token_runtime_error!(
+                            curr,
+                            ErrorKind::StackUnderflow,
+                            format!("Cannot pack {} items from a stack of {}", count, self.st_stack.len()).as_ref()
+                        );
+                    }
+
+                    let items = self.st_stack.split_off(self.st_stack.len() - count as usize);
+                    self.checked_push(curr, Object::List(Rc::new(items)))?;
+                }
+                TokenType::Unpack => {
+                    let popped = self.checked_pop(curr)?;
+                    if let Object::List(items) = popped {
+                        for item in items.iter() {
+                            self.checked_push(curr, item.clone())?;
+                        }
+                    } else {
+                        token_runtime_error!(
+                            curr,
+                            ErrorKind::Type,
+                            format!("Expecting list to unpack (got {:?})", popped).as_ref()
+                        );
+                    }
+                }
+                TokenType::Get => {
+                    let key = self.checked_pop(curr)?;
+                    let popped = self.checked_pop(curr)?;
+                    match (&popped, &key) {
+                        (Object::List(items), Object::Int(index)) => {
+                            if let Some(item) = usize::try_from(*index).ok().and_then(|i| items.get(i)) {
+                                let item = item.clone();
+                                self.checked_push(curr, item)?;
+                            } else {
+                                token_runtime_error!(
+                                    curr,
+                                    ErrorKind::OutOfRange,
+                                    format!("Index {} is out of range for list of length {}", index, items.len()).as_ref()
+                                );
+                            }
+                        }
+                        (Object::Map(entries), _) => {
+                            let key = self.checked_key(curr, &key)?;
+                            if let Some(value) = entries.get(&key) {
+                                let value = value.clone();
+                                self.checked_push(curr, value)?;
+                            } else {
+                                token_runtime_error!(
+                                    curr,
+                                    ErrorKind::MissingKey,
+                                    format!("Key {} not found in map", key.to_object().repr()).as_ref()
+                                );
+                            }
+                        }
+                        _ => {
+                            token_runtime_error!(
+                                curr,
+                                ErrorKind::Type,
+                                format!("Cannot perform this operation on types {:?} and {:?}", popped, key).as_ref()
+                            );
+                        }
+                    }
+                }
+                TokenType::Set => {
+                    let value = self.checked_pop(curr)?;
+                    let key = self.checked_pop(curr)?;
+                    let popped = self.checked_pop(curr)?;
+                    match (popped, &key) {
+                        (Object::List(mut items), Object::Int(index)) => {
+                            if let Some(item) = usize::try_from(*index).ok().and_then(|i| Rc::make_mut(&mut items).get_mut(i)) {
+                                *item = value;
+                                self.checked_push(curr, Object::List(items))?;
+                            } else {
+                                token_runtime_error!(
+                                    curr,
+                                    ErrorKind::OutOfRange,
+                                    format!("Index {} is out of range for list of length {}", index, items.len()).as_ref()
+                                );
+                            }
+                        }
+                        (Object::Map(mut entries), _) => {
+                            let key = self.checked_key(curr, &key)?;
+                            Rc::make_mut(&mut entries).insert(key, value);
+                            self.checked_push(curr, Object::Map(entries))?;
+                        }
+                        (popped, _) => {
+                            token_runtime_error!(
+                                curr,
+                                ErrorKind::Type,
+                                format!("Cannot perform this operation on types {:?} and {:?}", popped, key).as_ref()
+                            );
+                        }
+                    }
+                }
+                TokenType::NewMap => self.checked_push(curr, Object::Map(Rc::new(BTreeMap::new())))?,
+                TokenType::Del | TokenType::Has => {
+                    let key = self.checked_pop(curr)?;
+                    let popped = self.checked_pop(curr)?;
+                    if let Object::Map(mut entries) = popped {
+                        let key = self.checked_key(curr, &key)?;
+                        if let TokenType::Del = curr.type_ {
+                            if entries.contains_key(&key) {
+                                Rc::make_mut(&mut entries).remove(&key);
+                            }
+
+                            self.checked_push(curr, Object::Map(entries))?;
+                        } else {
+                            self.checked_push(curr, Object::Int(entries.contains_key(&key) as i64))?;
+                        }
+                    } else {
+                        token_runtime_error!(
+                            curr,
+                            ErrorKind::Type,
+                            format!("Expecting map (got {:?})", popped).as_ref()
+                        );
+                    }
+                }
+                TokenType::Keys | TokenType::Values => {
+                    let popped = self.checked_pop(curr)?;
+                    if let Object::Map(entries) = popped {
+                        let items = {
+                            if let TokenType::Keys = curr.type_ {
+                                entries.keys().map(Key::to_object).collect()
+                            } else {
+                                entries.values().cloned().collect()
+                            }
+                        };
+
+                        self.checked_push(curr, Object::List(Rc::new(items)))?;
+                    } else {
+                        token_runtime_error!(
+                            curr,
+                            ErrorKind::Type,
+                            format!("Expecting map (got {:?})", popped).as_ref()
+                        );
+                    }
+                }
+                TokenType::Len => {
+                    let popped = self.checked_pop(curr)?;
+                    match &popped {
+                        Object::List(items) => self.checked_push(curr, Object::Int(items.len() as i64))?,
+                        Object::Map(entries) => self.checked_push(curr, Object::Int(entries.len() as i64))?,
+                        Object::String(x)   => self.checked_push(curr, Object::Int(x.chars().count() as i64))?,
+                        _ => {
+                            token_runtime_error!(
+                                curr,
+                                ErrorKind::Type,
+                                format!("Cannot perform this operation on type {:?}", popped).as_ref()
+                            );
+                        }
+                    }
+                }
+                TokenType::Append => {
+                    let item = self.checked_pop(curr)?;
+                    let popped = self.checked_pop(curr)?;
+                    if let Object::List(mut items) = popped {
+                        Rc::make_mut(&mut items).push(item);
+                        self.checked_push(curr, Object::List(items))?;
+                    } else {
+                        token_runtime_error!(
+                            curr,
+                            ErrorKind::Type,
+                            format!("Expecting list to append to (got {:?})", popped).as_ref()
+                        );
+                    }
+                }
+
+                TokenType::Minus   => simple_binary!(self, curr, -, checked_sub),
+                TokenType::Slash   => {
+                    self.check_divisor(curr, "Division by zero")?;
+                    simple_binary!(self, curr, /, checked_div)
+                }
+                TokenType::Star    => simple_binary!(self, curr, *, checked_mul),
+                TokenType::Mod     => {
+                    self.check_divisor(curr, "Modulo by zero")?;
+                    simple_binary!(self, curr, %, checked_rem)
+                }
+                TokenType::And     => bitwise_binary!(self, curr, &),
+                TokenType::Or      => bitwise_binary!(self, curr, |),
+                TokenType::Xor     => bitwise_binary!(self, curr, ^),
+                TokenType::Shl | TokenType::Shr | TokenType::UShr | TokenType::RotL | TokenType::RotR => self.shift(curr)?,
+                TokenType::Sqrt | TokenType::Pow | TokenType::Exp | TokenType::Log |
+                TokenType::Sin | TokenType::Cos | TokenType::Tan | TokenType::Floor |
+                TokenType::Ceil | TokenType::Round | TokenType::Abs | TokenType::Min |
+                TokenType::Max | TokenType::Pi | TokenType::Euler => self.math(curr)?,
+                TokenType::PopCnt  => {
+                    let popped = self.checked_pop(curr)?;
+                    let count = match &popped {
+                        Object::BigInt(x) if x.sign() == Sign::Plus => x.magnitude().count_ones(),
+                        _ => self.narrow(curr, &popped)?.count_ones() as u64,
+                    };
+
+                    self.checked_push(curr, Object::Int(count as i64))?;
+                }
+                TokenType::Equal   => cmp_binary!(self, curr, ==),
+                TokenType::Greater => cmp_binary!(self, curr, >),
+                TokenType::Less    => cmp_binary!(self, curr, <),
+                
+                TokenType::Jump => {
+                    let jump_to = self.checked_pop(curr)?;
+                    match jump_to {
+                        Object::Int(amount)       => i = (i as i64 + amount       ) as usize % code.tokens.len(),
+                        Object::Float(amount)     => i = (i as i64 + amount as i64) as usize % code.tokens.len(),
+                        Object::String(label) => {
+                            if let Some(index) = code.labels.get(&label) {
+                                i = *index;
+                            } else {
+                                token_runtime_error!(
+                                    curr,
+                                    ErrorKind::UnknownLabel,
+                                    format!("Unknown label \"{}\"", label).as_ref()
+                                );
+                            }
+                        }
+                        Object::Code(code) => {
+                            self.call(&code, curr, ctx).await?;
+                            i += 1;
+                        }
+                        _ => {
+                            token_runtime_error!(
+                                curr,
+                                ErrorKind::Type,
+                                format!("Cannot jump to type {:?}", jump_to).as_ref()
+                            );
+                        }
+                    }
+                    
+                    continue;
+                }
+
+                TokenType::Question => {
+                    let jump_to = self.checked_pop(curr)?;
+                    let condition = self.checked_pop(curr)?;
+
+                    match jump_to {
+                        Object::Int(amount) => {
+                            if condition.is_truthy() {
+                                i = (i as i64 + amount) as usize % code.tokens.len();
+                                continue;
+                            }
+                        }
+                        Object::Float(amount) => {
+                            if condition.is_truthy() {
+                                i = (i as i64 + amount as i64) as usize % code.tokens.len();
+                                continue;
+                            }
+                        }
+                        Object::String(label) => {
+                            if let Some(index) = code.labels.get(&label) {
+                                if condition.is_truthy() {
+                                    i = *index;
+                                    continue;
+                                }
+                            } else {
+                                token_runtime_error!(
+                                    curr,
+                                    ErrorKind::UnknownLabel,
+                                    format!("Unknown label \"{}\"", label).as_ref()
+                                );
+                            }
+                        }
+                        Object::Code(code) => {
+                            if condition.is_truthy() {
+                                self.call(&code, curr, ctx).await?;
+                            }
+                        }
+                        _ => {
+                            token_runtime_error!(
+                                curr,
+                                ErrorKind::Type,
+                                format!("Cannot jump to type {:?}", jump_to).as_ref()
+                            );
+                        }
+                    }
+                }
+
+                TokenType::Colon => {
+                    let name_obj = self.checked_pop(curr)?;
+                    let code_obj = self.checked_pop(curr)?;
+
+                    if let Object::String(name) = name_obj {
+                        if keyword(&name).is_some() {
+                            token_runtime_error!(
+                                curr,
+                                ErrorKind::ReservedName,
+                                format!("Cannot redefine built-in operation \"{}\"", name).as_ref()
+                            );
+                        }
+
+                        if let Object::Code(code) = code_obj {
+                            self.functions.insert(name, Function::Code(code));
+                        } else {
+                            token_runtime_error!(
+                                curr,
+                                ErrorKind::Type,
+                                format!("Expecting code object as function body (got {:?})", code_obj).as_ref()
+                            );
+                        }
+                    } else {
+                        token_runtime_error!(
+                            curr,
+                            ErrorKind::Type,
+                            format!("Expecting string as function name (got {:?})", name_obj).as_ref()
+                        );
+                    }
+                }
+
+                TokenType::Try => {
+                    let handler = self.checked_pop_code(curr, "error handler")?;
+                    let body = self.checked_pop_code(curr, "guarded code")?;
+
+                    let snapshot = (
+                        self.st_stack.clone(), self.nd_stack.clone(),
+                        Rc::clone(&self.st_name), Rc::clone(&self.nd_name),
+                        self.stacks.clone()
+                    );
+
+                    match self.call(&body, curr, ctx).await {
+                        Err(Interrupt::Error(error)) if !error.kind.is_limit() => {
+                            (self.st_stack, self.nd_stack, self.st_name, self.nd_name, self.stacks) = snapshot;
+                            self.checked_push(curr, Object::String(Rc::from(error.message.as_str())))?;
+                            self.checked_push(curr, Object::String(Rc::from(format!("{:?}", error.kind))))?;
+                            self.call(&handler, curr, ctx).await?;
+                        }
+                        result => result?,
+                    }
+                }
+
+                TokenType::Identifier => {
+                    match self.functions.get(&curr.lexeme).cloned() {
+                        Some(Function::Code(code)) => {
+                            self.call(&code, curr, ctx).await?;
+                        }
+                        Some(Function::Native(function)) => {
+                            function(self).map_err(|e| Interrupt::Error(Box::new(e.located_at(curr))))?;
+                        }
+                        None => {
+                            token_runtime_error!(curr, ErrorKind::UndefinedFunction, "Undefined function");
+                        }
+                    }
+                }
+            }
+            
+            i += 1;
+        }
+
+        Ok(())
+    }
+}
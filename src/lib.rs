@@ -0,0 +1,187 @@
+use std::{cmp::Ordering, collections::{BTreeMap, HashMap}, fmt, rc::Rc};
+
+use num_bigint::BigInt;
+use scanner::Scanner;
+use tokens::Token;
+
+pub use buffer::SharedBuffer;
+pub use error::{ErrorKind, StaccError};
+pub use interpreter::{Hook, Interpreter, Limits, Overflow, Step};
+
+pub mod error;
+pub mod tokens;
+mod buffer;
+mod interpreter;
+mod scanner;
+mod utils;
+
+#[derive(Debug, Clone)]
+pub struct Code {
+    pub tokens: Vec<Token>,
+    pub labels: HashMap<Rc<str>, usize>
+}
+
+impl Code {
+    pub fn new(tokens: Vec<Token>, labels: HashMap<Rc<str>, usize>) -> Self {
+        Code { tokens, labels }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Key {
+    Int(i64),
+    String(Rc<str>)
+}
+
+impl Key {
+    pub fn from_object(object: &Object) -> Option<Self> {
+        match object {
+            Object::Int(x)    => Some(Key::Int(*x)),
+            Object::String(x) => Some(Key::String(Rc::clone(x))),
+            _ => None
+        }
+    }
+
+    pub fn to_object(&self) -> Object {
+        match self {
+            Key::Int(x)    => Object::Int(*x),
+            Key::String(x) => Object::String(Rc::clone(x)),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Object {
+    Int(i64),
+    BigInt(Rc<BigInt>),
+    Float(f64),
+    String(Rc<str>),
+    Code(Code),
+    List(Rc<Vec<Object>>),
+    Map(Rc<BTreeMap<Key, Object>>)
+}
+
+impl Object {
+    pub fn is_number(&self) -> bool {
+        matches!(self, Object::Int(_) | Object::BigInt(_) | Object::Float(_))
+    }
+
+    pub fn to_f64(&self) -> Option<f64> {
+        match self {
+            Object::Int(x)    => Some(*x as f64),
+            Object::BigInt(x) => Some(utils::big_to_f64(x)),
+            Object::Float(x)  => Some(*x),
+            _ => None
+        }
+    }
+
+    pub fn is_truthy(&self) -> bool {
+        match self {
+            Object::Int(x)   => *x != 0,
+            Object::BigInt(_) => true,
+            Object::Float(x) => *x != 0.0,
+            Object::String(_) | Object::Code(_) => true,
+            Object::List(x)  => !x.is_empty(),
+            Object::Map(x)   => !x.is_empty(),
+        }
+    }
+
+    pub fn repr(&self) -> String {
+        match self {
+            Object::Int(x)        => x.to_string(),
+            Object::BigInt(x)     => x.to_string(),
+            Object::Float(x)      => format!("{:?}", x),
+            Object::String(x) => format!("{:?}", x),
+            Object::Code(_)             => String::from("<Code object>"),
+            Object::List(x)   => {
+                let items = x.iter().map(Object::repr).collect::<Vec<String>>();
+                format!("[{}]", items.join(", "))
+            }
+            Object::Map(x)    => {
+                let items = x.iter().map(|(k, v)| format!("{}: {}", k.to_object().repr(), v.repr())).collect::<Vec<String>>();
+                format!("{{{}}}", items.join(", "))
+            }
+        }
+    }
+}
+
+impl PartialEq for Object {
+    fn eq(&self, other: &Self) -> bool {
+        self.partial_cmp(other) == Some(Ordering::Equal)
+    }
+}
+
+impl PartialOrd for Object {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        match (self, other) {
+            (Object::Int(x),    Object::Int(y))    => x.partial_cmp(y),
+            (Object::Int(x),    Object::BigInt(y)) => BigInt::from(*x).partial_cmp(y),
+            (Object::BigInt(x), Object::Int(y))    => x.as_ref().partial_cmp(&BigInt::from(*y)),
+            (Object::BigInt(x), Object::BigInt(y)) => x.partial_cmp(y),
+            (Object::Int(x),    Object::Float(y))  => (*x as f64).partial_cmp(y),
+            (Object::Float(x),  Object::Int(y))    => x.partial_cmp(&(*y as f64)),
+            (Object::BigInt(x), Object::Float(y))  => utils::big_to_f64(x).partial_cmp(y),
+            (Object::Float(x),  Object::BigInt(y)) => x.partial_cmp(&utils::big_to_f64(y)),
+            (Object::Float(x),  Object::Float(y))  => x.partial_cmp(y),
+            (Object::String(x), Object::String(y)) => x.partial_cmp(y),
+            (Object::List(x),   Object::List(y))   => x.partial_cmp(y),
+            (Object::Map(x),    Object::Map(y))    => x.partial_cmp(y),
+            _ => None
+        }
+    }
+}
+
+impl fmt::Display for Object {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Object::Int(x)        => write!(f, "{}", x),
+            Object::BigInt(x)     => write!(f, "{}", x),
+            Object::Float(x)      => write!(f, "{}", x),
+            Object::String(x) => write!(f, "{}", x),
+            Object::Code(_)             => write!(f, "<Code object>"),
+            Object::List(_) | Object::Map(_) => write!(f, "{}", self.repr()),
+        }
+    }
+}
+
+impl From<BigInt> for Object {
+    fn from(value: BigInt) -> Self {
+        if let Ok(x) = i64::try_from(&value) {
+            Object::Int(x)
+        } else {
+            Object::BigInt(Rc::new(value))
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum Interrupt {
+    Error(Box<StaccError>),
+    Exit(i32),
+    Return,
+    Halt
+}
+
+impl Interrupt {
+    pub fn called_from(self, token: &Token) -> Self {
+        match self {
+            Interrupt::Error(mut error) => {
+                error.called_from(token);
+                Interrupt::Error(error)
+            }
+            other => other
+        }
+    }
+}
+
+pub fn compile(source: &str, name: &str) -> Result<Code, Vec<StaccError>> {
+    let source = source.to_string();
+    let mut scanner = Scanner::new(&source, name);
+    scanner.scan_tokens();
+
+    if scanner.had_error() {
+        Err(scanner.errors)
+    } else {
+        Ok(Code::new(scanner.tokens, scanner.labels))
+    }
+}
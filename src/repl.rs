@@ -0,0 +1,50 @@
+use std::{io::{stdin, stdout, BufRead, Error, Write}, process::exit};
+
+use stacc::{compile, Interpreter, Interrupt, Object, Overflow};
+
+pub fn format_stack(stack: &[Object]) -> String {
+    let items = stack.iter().map(Object::repr).collect::<Vec<String>>();
+    format!("[{}]", items.join(", "))
+}
+
+pub fn run(overflow: Overflow) -> Result<(), Error> {
+    let mut interpreter = Interpreter::new();
+    interpreter.set_overflow(overflow);
+    let mut line = String::new();
+
+    loop {
+        print!("> ");
+        stdout().flush()?;
+
+        line.clear();
+        if stdin().lock().read_line(&mut line)? == 0 {
+            println!();
+            return Ok(());
+        }
+
+        let source = line.trim_end_matches(['\n', '\r']).to_string();
+        if source.trim().is_empty() {
+            continue;
+        }
+
+        let code = match compile(&source, "<stdin>") {
+            Ok(code) => code,
+            Err(errors) => {
+                for error in errors {
+                    eprintln!("{}", error);
+                }
+
+                continue;
+            }
+        };
+
+        match interpreter.run(&code) {
+            Ok(()) | Err(Interrupt::Return | Interrupt::Halt) => (),
+            Err(Interrupt::Error(error)) => eprintln!("{}", error),
+            Err(Interrupt::Exit(status)) => exit(status),
+        }
+
+        println!(" FIRST STACK: {}", format_stack(interpreter.primary()));
+        println!("SECOND STACK: {}", format_stack(interpreter.secondary()));
+    }
+}
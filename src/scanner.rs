@@ -1,12 +1,17 @@
 use std::collections::HashMap;
 use std::rc::Rc;
 
-use crate::tokens::{Token, TokenType};
-use crate::utils::{error, is_alpha, is_alphanumeric, is_digit, substring};
+use num_bigint::BigInt;
+
+use crate::error::{ErrorKind, Span, StaccError};
+use crate::tokens::{keyword, Token, TokenType};
+use crate::utils::{is_alpha, is_alphanumeric, is_digit};
 use crate::Code;
 
-pub struct Scanner<'a> {
-    source: &'a String,
+pub struct Scanner {
+    source: Vec<char>,
+    shared_source: Rc<str>,
+    name: Rc<str>,
     pub tokens: Vec<Token>,
     pub labels: HashMap<Rc<str>, usize>,
     start_positions: Vec<usize>,
@@ -14,65 +19,94 @@ pub struct Scanner<'a> {
     start: usize,
     curr:  usize,
     line:  usize,
+    start_line: usize,
+    boundary: bool,
 
-    pub had_error: bool
+    pub errors: Vec<StaccError>
 }
 
-impl<'a> Scanner<'a> {
-    pub fn new(source: &'a String) -> Self {
+impl Scanner {
+    pub fn new(source: &String, name: &str) -> Self {
         Scanner {
-            source, tokens: Vec::new(), start_positions: Vec::new(),
-            labels: HashMap::new(), start: 0, curr: 0, line: 0, had_error: false
+            source: source.chars().collect(), shared_source: Rc::from(source.as_ref()), name: Rc::from(name),
+            tokens: Vec::new(), start_positions: Vec::new(), labels: HashMap::new(),
+            start: 0, curr: 0, line: 0, start_line: 0, boundary: true, errors: Vec::new()
         }
     }
 
+    pub fn had_error(&self) -> bool {
+        !self.errors.is_empty()
+    }
+
+    fn new_token(&self, type_: TokenType, lexeme: Rc<str>, pos: usize, end: usize, line: usize) -> Token {
+        Token::new(
+            Rc::clone(&self.shared_source), Rc::clone(&self.name),
+            type_, lexeme, pos, end, line
+        )
+    }
+
     fn is_at_end(&self) -> bool {
         self.curr >= self.source.len()
     }
 
     fn advance(&mut self) -> char {
-        let c = self.source.chars().nth(self.curr).unwrap();
+        let c = self.source[self.curr];
         self.curr += 1;
         c
     }
 
+    fn substring(&self, a: usize, b: usize) -> String {
+        self.source[a..b].iter().collect()
+    }
+
     fn get_substring(&mut self) -> Rc<str> {
-        substring(&self.source, self.start, self.curr).into()
+        self.substring(self.start, self.curr).into()
     }
 
     fn add_token(&mut self, type_: TokenType) {
         let lexeme = self.get_substring();
-        self.tokens.push(Token::new(
-            Rc::from(self.source.as_ref()), 
+        let token = self.new_token(
             type_, lexeme, 
-            self.start - self.start_positions[self.line], 
-            self.curr - self.start_positions[self.line], self.line
-        ));
+            self.start - self.start_positions[self.start_line], 
+            self.curr - self.start_positions[self.start_line], self.start_line
+        );
+
+        self.tokens.push(token);
     }
 
     fn peek(&self) -> char {
         if self.is_at_end() {
             '\0'
         } else {
-            self.source.chars().nth(self.curr).unwrap()
+            self.source[self.curr]
         }
     }
 
+    fn peek_at(&self, offset: usize) -> char {
+        self.source.get(self.curr + offset).copied().unwrap_or('\0')
+    }
+
+    fn peek_next(&self) -> char {
+        self.peek_at(1)
+    }
+
     fn error(&mut self, msg: &str) {
-        error(
-            &Rc::from(self.source.as_ref()), msg, 
-            self.start - self.start_positions[self.line], 
-            self.curr - self.start, self.line
-        );
-        
-        self.had_error = true;
+        self.error_at(msg, self.start, self.curr - self.start, self.start_line);
     }
 
-    fn string(&mut self, ch: char) {
+    fn error_at(&mut self, msg: &str, start: usize, len: usize, line: usize) {
+        let pos = start - self.start_positions[line];
+        self.errors.push(StaccError::new(
+            ErrorKind::Syntax, msg, Span::new(line, pos, pos + len),
+            Rc::clone(&self.name), Rc::clone(&self.shared_source)
+        ));
+    }
+
+    fn label(&mut self) -> bool {
         let mut back_slash = false;
         loop {
             let c = self.peek();
-            if self.is_at_end() || (c == ch && !back_slash) {
+            if self.is_at_end() || (c == ']' && !back_slash) {
                 break;
             }
 
@@ -81,11 +115,7 @@ impl<'a> Scanner<'a> {
 
             match c {
                 '\n' => self.line += 1,
-                '\\' => {
-                    if !old_backslash {
-                        back_slash = true;
-                    }
-                }
+                '\\' if !old_backslash => back_slash = true,
                 _ => (),
             }
 
@@ -93,42 +123,325 @@ impl<'a> Scanner<'a> {
         }
 
         if self.is_at_end() {
-            self.error("Unterminated string");
-            return;
+            self.error("Unterminated label");
+            return false;
         }
 
         self.advance();
+        true
     }
     
-    fn number(&mut self) {
-        while is_digit(self.peek()) {
-            self.advance();
+    fn block_comment(&mut self) {
+        let mut depth = 1usize;
+        while depth > 0 && !self.is_at_end() {
+            match self.advance() {
+                '('  => depth += 1,
+                ')'  => depth -= 1,
+                '\n' => self.line += 1,
+                _ => (),
+            }
         }
 
-        let c = self.peek();
-        if c == '.' {
-            self.advance();
+        if depth > 0 {
+            self.error_at("Unterminated comment", self.start, 1, self.start_line);
+        }
+    }
+
+    fn escape(&mut self, value: &mut String) {
+        let escape_start = self.curr - 1;
+        let escape_line  = self.line;
+
+        if self.is_at_end() {
+            return;
+        }
+
+        let c = self.advance();
+        match c {
+            'n'  => value.push('\n'),
+            't'  => value.push('\t'),
+            'r'  => value.push('\r'),
+            '0'  => value.push('\0'),
+            '\\' => value.push('\\'),
+            '"'  => value.push('"'),
+            '\'' => value.push('\''),
+            'u'  => {
+                if self.peek() != '{' {
+                    self.error_at(
+                        "Expecting '{' after unicode escape", 
+                        escape_start, self.curr - escape_start, escape_line
+                    );
+                    return;
+                }
 
-            if is_digit(self.peek()) {
-                loop {
+                self.advance();
+                while self.peek() != '}' && self.peek() != '"' && !self.is_at_end() {
                     self.advance();
+                }
 
-                    if !is_digit(self.peek()) {
-                        break;
+                if self.peek() != '}' {
+                    self.error_at(
+                        "Unterminated unicode escape", 
+                        escape_start, self.curr - escape_start, escape_line
+                    );
+                    return;
+                }
+
+                self.advance();
+
+                let digits = self.substring(escape_start + 3, self.curr - 1);
+                let parsed = {
+                    if digits.is_empty() || digits.len() > 6 {
+                        None
+                    } else {
+                        u32::from_str_radix(&digits, 16).ok().and_then(char::from_u32)
                     }
+                };
+
+                if let Some(ch) = parsed {
+                    value.push(ch);
+                } else {
+                    self.error_at(
+                        "Invalid unicode escape", 
+                        escape_start, self.curr - escape_start, escape_line
+                    );
                 }
-            } else {
-                self.error("Expecting digits after decimal point");
             }
-            
-            let parsed = self.get_substring().parse().unwrap();
-            self.add_token(TokenType::Float(parsed));
-        } else {
-            let parsed = self.get_substring().parse().unwrap();
+            _ => {
+                if c == '\n' {
+                    self.line += 1;
+                }
+
+                self.error_at("Invalid escape sequence", escape_start, 2, escape_line);
+            }
+        }
+    }
+
+    fn string_literal(&mut self) {
+        let mut value = String::new();
+        loop {
+            if self.is_at_end() {
+                self.error_at("Unterminated string", self.start, 1, self.start_line);
+                return;
+            }
+
+            let c = self.advance();
+            match c {
+                '"'  => break,
+                '\\' => self.escape(&mut value),
+                _ => {
+                    if c == '\n' {
+                        self.line += 1;
+                    }
+
+                    value.push(c);
+                }
+            }
+        }
+
+        self.add_token(TokenType::String(value.into()));
+    }
+
+    fn raw_string(&mut self) {
+        while self.peek() != '`' && !self.is_at_end() {
+            if self.advance() == '\n' {
+                self.line += 1;
+            }
+        }
+
+        if self.is_at_end() {
+            self.error_at("Unterminated raw string", self.start, 1, self.start_line);
+            return;
+        }
+
+        self.advance();
+
+        let value = self.substring(self.start + 1, self.curr - 1);
+        self.add_token(TokenType::String(value.into()));
+    }
+
+    fn digits(&mut self, radix: u32) -> String {
+        let mut digits = String::new();
+        while self.peek().is_digit(radix) || self.peek() == '_' {
+            digits.push(self.advance());
+        }
+
+        digits
+    }
+
+    fn check_separators(&mut self, digits: &str) -> bool {
+        if digits.starts_with('_') || digits.ends_with('_') || digits.contains("__") {
+            self.error("Digit separators must be placed between digits");
+            return false;
+        }
+
+        true
+    }
+
+    fn check_number_end(&mut self) -> bool {
+        if !is_alphanumeric(self.peek()) {
+            return true;
+        }
+
+        while is_alphanumeric(self.peek()) {
+            self.advance();
+        }
+
+        self.error("Invalid character in number literal");
+        false
+    }
+
+    fn integer(&mut self, digits: &str, negative: bool, radix: u32) {
+        let mut parsed = BigInt::parse_bytes(digits.replace('_', "").as_bytes(), radix).unwrap();
+        if negative {
+            parsed = -parsed;
+        }
+
+        if let Ok(parsed) = i64::try_from(&parsed) {
             self.add_token(TokenType::Int(parsed));
+        } else {
+            self.add_token(TokenType::BigInt(Rc::new(parsed)));
         }
     }
 
+    fn number(&mut self) {
+        let negative = self.get_substring().starts_with('-');
+        if !negative {
+            self.curr -= 1;
+        }
+
+        if self.peek() == '0' {
+            let radix = match self.peek_next() {
+                'x' | 'X' => Some((16, "hexadecimal")),
+                'b' | 'B' => Some((2, "binary")),
+                'o' | 'O' => Some((8, "octal")),
+                _ => None
+            };
+
+            if let Some((radix, name)) = radix {
+                self.advance();
+                self.advance();
+
+                let digits = self.digits(radix);
+                if digits.is_empty() {
+                    self.error(format!("Expecting {} digits", name).as_ref());
+                } else if self.check_separators(&digits) && self.check_number_end() {
+                    self.integer(&digits, negative, radix);
+                }
+
+                return;
+            }
+        }
+
+        let mut literal = self.digits(10);
+        if !self.check_separators(&literal) {
+            return;
+        }
+
+        let mut is_float = false;
+        if self.peek() == '.' {
+            self.advance();
+            is_float = true;
+
+            let fraction = self.digits(10);
+            if fraction.is_empty() {
+                self.error("Expecting digits after decimal point");
+                return;
+            }
+
+            if !self.check_separators(&fraction) {
+                return;
+            }
+
+            literal = format!("{}.{}", literal, fraction);
+        }
+
+        let c = self.peek();
+        let next = self.peek_next();
+        if (c == 'e' || c == 'E') && (is_digit(next) || ((next == '+' || next == '-') && is_digit(self.peek_at(2)))) {
+            self.advance();
+            is_float = true;
+
+            let mut exponent = String::new();
+            if next == '+' || next == '-' {
+                exponent.push(self.advance());
+            }
+
+            let digits = self.digits(10);
+            if !self.check_separators(&digits) {
+                return;
+            }
+
+            exponent.push_str(&digits);
+            literal = format!("{}e{}", literal, exponent);
+        }
+
+        if !self.check_number_end() {
+            return;
+        }
+
+        if !is_float {
+            self.integer(&literal, negative, 10);
+            return;
+        }
+
+        let mut parsed: f64 = literal.replace('_', "").parse().unwrap();
+        if parsed.is_infinite() {
+            self.error("Float literal is out of range");
+            return;
+        }
+
+        if negative {
+            parsed = -parsed;
+        }
+
+        self.add_token(TokenType::Float(parsed));
+    }
+
+    fn code_block(&mut self) {
+        let block_start = self.start;
+        let block_line  = self.line;
+
+        let outer_tokens = std::mem::take(&mut self.tokens);
+        let outer_labels = std::mem::take(&mut self.labels);
+
+        self.boundary = true;
+
+        let mut closed = false;
+        while !self.is_at_end() {
+            self.start = self.curr;
+            self.start_line = self.line;
+
+            if self.peek() == '}' {
+                self.advance();
+                closed = true;
+                break;
+            }
+
+            let count = self.tokens.len();
+            self.scan_token();
+            self.boundary = self.tokens.len() == count;
+        }
+
+        let eof = self.new_token(TokenType::EOF, Rc::from(""), 0, 1, self.line);
+        self.tokens.push(eof);
+
+        let tokens = std::mem::replace(&mut self.tokens, outer_tokens);
+        let labels = std::mem::replace(&mut self.labels, outer_labels);
+
+        if !closed {
+            self.error_at("Unterminated code block", block_start, 1, block_line);
+            return;
+        }
+
+        let pos = block_start - self.start_positions[block_line];
+        let token = self.new_token(
+            TokenType::Code(Code::new(tokens, labels)), Rc::from("{"), 
+            pos, pos + 1, block_line
+        );
+
+        self.tokens.push(token);
+    }
+
     fn scan_token(&mut self) {
         let c = self.advance();
         match c {
@@ -146,26 +459,26 @@ impl<'a> Scanner<'a> {
             '+' => self.add_token(TokenType::Plus),
             '|' => self.add_token(TokenType::Or),
             '&' => self.add_token(TokenType::And),
-            '-' => self.add_token(TokenType::Minus),
+            '-' => {
+                if is_digit(self.peek()) && self.boundary {
+                    self.number();
+                } else {
+                    self.add_token(TokenType::Minus);
+                }
+            }
             '!' => self.add_token(TokenType::Bang),
             '<' => self.add_token(TokenType::Less),
             '>' => self.add_token(TokenType::Greater),
             '/' => self.add_token(TokenType::Slash),
             '#' => self.add_token(TokenType::Hash),
             '$' => self.add_token(TokenType::Print),
-            '"'  => {
-                self.string('"');
-                self.start += 1;
-                self.curr -= 1;
-                
-                let parsed = self.get_substring();
-                self.add_token(TokenType::String(parsed));
-
-                self.start -= 1;
-                self.curr += 1;
-            }
+            '"' => self.string_literal(),
+            '`' => self.raw_string(),
             '['  => {
-                self.string(']');
+                if !self.label() {
+                    return;
+                }
+
                 self.start += 1;
                 self.curr -= 1;
                 
@@ -175,41 +488,16 @@ impl<'a> Scanner<'a> {
                 self.start -= 1;
                 self.curr += 1;
             }
-            ' ' | '\r' | '\t' => (),
-            '\n' => self.line += 1,
-
-            '{' => {
-                loop {
-                    let c = self.peek();
-                    if self.is_at_end() || (c == '}') {
-                        break;
-                    }
-
-                    if c == '\n' {
-                        self.line += 1;
-                    }
-        
+            '\\' => {
+                while self.peek() != '\n' && !self.is_at_end() {
                     self.advance();
                 }
-
-                if self.is_at_end() {
-                    self.error("Unterminated code block");
-                    return;
-                }
-
-                self.advance();
-                
-                self.start += 1;
-                self.curr -= 1;
-                let source = self.get_substring().to_string();
-                self.start -= 1;
-                self.curr += 1;
-
-                let mut scanner = Scanner::new(&source);
-                scanner.scan_tokens();
-                self.start = self.curr;
-                self.add_token(TokenType::Code(Code::new(scanner.tokens, scanner.labels)));
             }
+            '(' => self.block_comment(),
+            ' ' | '\r' | '\t' => (),
+            '\n' => self.line += 1,
+
+            '{' => self.code_block(),
 
             _ => {
                 if is_digit(c) {
@@ -218,8 +506,9 @@ impl<'a> Scanner<'a> {
                     while is_alphanumeric(self.peek()) {
                         self.advance();
                     }
-            
-                    self.add_token(TokenType::Identifier);
+
+                    let type_ = keyword(&self.get_substring()).unwrap_or(TokenType::Identifier);
+                    self.add_token(type_);
                 } else {
                     self.error("Unexpected character");
                 }
@@ -229,8 +518,8 @@ impl<'a> Scanner<'a> {
 
     fn get_start_positions(&mut self) {
         self.start_positions.push(0);
-        for (i, c) in self.source.chars().enumerate() {
-            if c == '\n' {
+        for (i, c) in self.source.iter().enumerate() {
+            if *c == '\n' {
                 self.start_positions.push(i + 1);
             }
         }
@@ -241,13 +530,13 @@ impl<'a> Scanner<'a> {
         
         while !self.is_at_end() {
             self.start = self.curr;
+            self.start_line = self.line;
+            let count = self.tokens.len();
             self.scan_token();
+            self.boundary = self.tokens.len() == count;
         }
 
-        self.tokens.push(Token::new(
-            Rc::from(self.source.as_ref()), 
-            TokenType::EOF, Rc::from(""), 
-            0, 1, self.line
-        ));
+        let eof = self.new_token(TokenType::EOF, Rc::from(""), 0, 1, self.line);
+        self.tokens.push(eof);
     }
 }
\ No newline at end of file
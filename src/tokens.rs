@@ -1,7 +1,10 @@
 use std::rc::Rc;
 
+use num_bigint::BigInt;
+
 use crate::Code;
 
+#[allow(clippy::upper_case_acronyms)]
 #[derive(Debug, Clone)]
 pub enum TokenType {
     Comma, Dot, Minus, Plus, Semicolon,
@@ -10,15 +13,39 @@ pub enum TokenType {
     Equal, Greater, Less, 
     Hash, And, Or, Jump,
 
+    ReadLine, ReadAll, ReadChar, Exit, Return, Halt, Try, Overflow,
+    Pack, Unpack, Get, Len, Append,
+    NewMap, Set, Del, Has, Keys, Values,
+    Swap, Over, Rot, Drop, Depth, Pick, Roll,
+    NdSwap, NdOver, NdRot, NdPick, NdRoll,
+    NdPeek, NdDup, NdDepth, NdClear,
+    NewStack, Select, NdSelect, Move,
+    Xor, Shl, Shr, UShr, PopCnt, RotL, RotR,
+    Sqrt, Pow, Exp, Log, Sin, Cos, Tan, Floor, Ceil, Round, Abs, Min, Max, Pi, Euler,
+
     Identifier, String(Rc<str>), 
-    Int(i64), Float(f64), Code(Code),
+    Int(i64), BigInt(Rc<BigInt>), Float(f64), Code(Code),
     
     EOF
 }
 
+impl TokenType {
+    pub fn name(&self) -> String {
+        match self {
+            TokenType::String(_) => String::from("String"),
+            TokenType::Int(_)    => String::from("Int"),
+            TokenType::BigInt(_) => String::from("BigInt"),
+            TokenType::Float(_)  => String::from("Float"),
+            TokenType::Code(_)   => String::from("Code"),
+            other => format!("{:?}", other),
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct Token {
     pub source: Rc<str>,
+    pub source_name: Rc<str>,
     pub type_: TokenType,
     pub lexeme: Rc<str>,
     pub pos: usize,
@@ -33,7 +60,74 @@ impl std::fmt::Debug for Token {
 }
 
 impl Token {
-    pub fn new(source: Rc<str>, type_: TokenType, lexeme: Rc<str>, pos: usize, end: usize, line: usize) -> Self {
-        Token { source, type_, lexeme, pos, end, line }
+    pub fn new(source: Rc<str>, source_name: Rc<str>, type_: TokenType, lexeme: Rc<str>, pos: usize, end: usize, line: usize) -> Self {
+        Token { source, source_name, type_, lexeme, pos, end, line }
+    }
+}
+
+pub fn keyword(name: &str) -> Option<TokenType> {
+    match name {
+        "readline" => Some(TokenType::ReadLine),
+        "readall"  => Some(TokenType::ReadAll),
+        "readchar" => Some(TokenType::ReadChar),
+        "exit"     => Some(TokenType::Exit),
+        "return"   => Some(TokenType::Return),
+        "halt"     => Some(TokenType::Halt),
+        "try"      => Some(TokenType::Try),
+        "overflow" => Some(TokenType::Overflow),
+        "pack"     => Some(TokenType::Pack),
+        "unpack"   => Some(TokenType::Unpack),
+        "get"      => Some(TokenType::Get),
+        "len"      => Some(TokenType::Len),
+        "append"   => Some(TokenType::Append),
+        "map"      => Some(TokenType::NewMap),
+        "set"      => Some(TokenType::Set),
+        "del"      => Some(TokenType::Del),
+        "has"      => Some(TokenType::Has),
+        "keys"     => Some(TokenType::Keys),
+        "values"   => Some(TokenType::Values),
+        "swap"     => Some(TokenType::Swap),
+        "over"     => Some(TokenType::Over),
+        "rot"      => Some(TokenType::Rot),
+        "drop"     => Some(TokenType::Drop),
+        "depth"    => Some(TokenType::Depth),
+        "pick"     => Some(TokenType::Pick),
+        "roll"     => Some(TokenType::Roll),
+        "ndswap"   => Some(TokenType::NdSwap),
+        "ndover"   => Some(TokenType::NdOver),
+        "ndrot"    => Some(TokenType::NdRot),
+        "ndpick"   => Some(TokenType::NdPick),
+        "ndroll"   => Some(TokenType::NdRoll),
+        "ndpeek"   => Some(TokenType::NdPeek),
+        "nddup"    => Some(TokenType::NdDup),
+        "nddepth"  => Some(TokenType::NdDepth),
+        "ndclear"  => Some(TokenType::NdClear),
+        "newstack" => Some(TokenType::NewStack),
+        "select"   => Some(TokenType::Select),
+        "ndselect" => Some(TokenType::NdSelect),
+        "move"     => Some(TokenType::Move),
+        "xor"      => Some(TokenType::Xor),
+        "shl"      => Some(TokenType::Shl),
+        "shr"      => Some(TokenType::Shr),
+        "ushr"     => Some(TokenType::UShr),
+        "popcnt"   => Some(TokenType::PopCnt),
+        "rotl"     => Some(TokenType::RotL),
+        "rotr"     => Some(TokenType::RotR),
+        "sqrt"     => Some(TokenType::Sqrt),
+        "pow"      => Some(TokenType::Pow),
+        "exp"      => Some(TokenType::Exp),
+        "log"      => Some(TokenType::Log),
+        "sin"      => Some(TokenType::Sin),
+        "cos"      => Some(TokenType::Cos),
+        "tan"      => Some(TokenType::Tan),
+        "floor"    => Some(TokenType::Floor),
+        "ceil"     => Some(TokenType::Ceil),
+        "round"    => Some(TokenType::Round),
+        "abs"      => Some(TokenType::Abs),
+        "min"      => Some(TokenType::Min),
+        "max"      => Some(TokenType::Max),
+        "pi"       => Some(TokenType::Pi),
+        "euler"    => Some(TokenType::Euler),
+        _ => None
     }
 }
\ No newline at end of file
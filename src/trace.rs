@@ -0,0 +1,103 @@
+use std::io::{stderr, Write};
+use std::ops::RangeInclusive;
+
+use stacc::error::{ErrorKind, StaccError};
+use stacc::tokens::TokenType;
+use stacc::{Hook, Interpreter, Interrupt, Object, Step};
+
+use crate::repl::format_stack;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum TraceFormat {
+    Text,
+    Json
+}
+
+pub struct Tracer {
+    pub format: TraceFormat,
+    pub tops_only: bool,
+    pub function: Option<String>,
+    pub lines: Option<RangeInclusive<usize>>
+}
+
+fn json_string(value: &str) -> String {
+    let mut escaped = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"'  => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped.push('"');
+    escaped
+}
+
+fn json_stack(stack: &[Object]) -> String {
+    let items = stack.iter().map(|x| json_string(&x.repr())).collect::<Vec<String>>();
+    format!("[{}]", items.join(","))
+}
+
+impl Tracer {
+    pub fn new(format: TraceFormat) -> Self {
+        Tracer { format, tops_only: false, function: None, lines: None }
+    }
+
+    fn stack<'a>(&self, stack: &'a [Object]) -> &'a [Object] {
+        if self.tops_only {
+            &stack[stack.len().saturating_sub(1)..]
+        } else {
+            stack
+        }
+    }
+
+    fn record(&self, interpreter: &Interpreter, step: &Step, function: &str) -> String {
+        let token = step.token;
+        let st = self.stack(interpreter.primary());
+        let nd = self.stack(interpreter.secondary());
+
+        match self.format {
+            TraceFormat::Text => format!(
+                "{} (line {}, pos {}) in {}, depth {}: {} {} st={} nd={}",
+                token.source_name, token.line + 1, token.pos, function, step.depth,
+                token.type_.name(), token.lexeme, format_stack(st), format_stack(nd)
+            ),
+            TraceFormat::Json => format!(
+                "{{\"kind\":{},\"lexeme\":{},\"source\":{},\"line\":{},\"pos\":{},\"function\":{},\"depth\":{},\"st\":{},\"nd\":{}}}",
+                json_string(&token.type_.name()), json_string(&token.lexeme), json_string(&token.source_name),
+                token.line + 1, token.pos, json_string(function), step.depth, json_stack(st), json_stack(nd)
+            ),
+        }
+    }
+}
+
+impl Hook for Tracer {
+    fn before_token(&mut self, interpreter: &mut Interpreter, step: &Step) -> Result<(), Interrupt> {
+        if let TokenType::EOF = step.token.type_ {
+            return Ok(());
+        }
+
+        let function = interpreter.frames().last().map(|f| f.to_string()).unwrap_or(String::from("<main>"));
+
+        if self.function.as_ref().is_some_and(|f| *f != function) {
+            return Ok(());
+        }
+
+        if self.lines.as_ref().is_some_and(|lines| !lines.contains(&(step.token.line + 1))) {
+            return Ok(());
+        }
+
+        if let Err(e) = writeln!(stderr(), "{}", self.record(interpreter, step, &function)) {
+            return Err(Interrupt::Error(Box::new(StaccError::at_token(
+                ErrorKind::Io, format!("Could not write trace: {}", e).as_ref(), step.token
+            ))));
+        }
+
+        Ok(())
+    }
+}
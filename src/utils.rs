@@ -1,64 +1,101 @@
-use std::{cmp::max, rc::Rc};
+use std::{io::{self, BufRead}, rc::Rc};
 
-pub fn substring(string: &String, a: usize, b: usize) -> String {
-    string.chars().skip(a).take(b - a).collect()
+use num_bigint::{BigInt, Sign};
+use num_traits::ToPrimitive;
+
+use crate::tokens::{Token, TokenType};
+use crate::Object;
+
+pub fn big_to_f64(x: &BigInt) -> f64 {
+    x.to_f64().unwrap_or(f64::NAN)
 }
 
-pub fn report(source: &Rc<str>, msg: &str, type_: &str, pos: usize, len: usize, line: usize) {
-    let lines = source.lines().collect::<Vec<&str>>();
-    let iter_range = {
-        if lines.len() < 5 {
-            0..lines.len()
-        } else {
-            if line <= 2 {
-                0..5
-            } else if line >= lines.len() - 3 {
-                (lines.len() - 5)..lines.len()
-            } else {
-                (line - 2)..(line + 3)
-            }
-        }
-    };
+pub fn wrap_to_i64(x: &BigInt) -> i64 {
+    let (sign, digits) = x.to_u64_digits();
+    let low = digits.first().copied().unwrap_or(0) as i64;
+    if sign == Sign::Minus { low.wrapping_neg() } else { low }
+}
 
-    let linelen = max((iter_range.end as f64).log10().ceil() as usize, 1);
+#[doc(hidden)]
+#[macro_export]
+macro_rules! token_runtime_error {
+    ($token: expr, $kind: expr, $msg: expr) => {
+        return Err($crate::Interrupt::Error(Box::new(
+            $crate::error::StaccError::at_token($kind, $msg, $token)
+        )));
+    };
+}
 
-    println!("{} (line {}, pos {}): {}", type_, line + 1, pos, msg);
+/// Counts the items of a collection, including the items of nested collections,
+/// giving up as soon as the count exceeds `max`.
+pub fn count_items(object: &Object, max: usize) -> usize {
+    let mut count = 0;
+    let mut pending = vec![object];
+    while let Some(object) = pending.pop() {
+        let items: Box<dyn Iterator<Item = &Object>> = match object {
+            Object::List(x) => Box::new(x.iter()),
+            Object::Map(x)  => Box::new(x.values()),
+            _ => continue
+        };
 
-    for l in iter_range {
-        println!("{:linelen$} | {}", l + 1, lines[l].trim_end());
+        for item in items {
+            count += 1;
+            if count > max {
+                return count;
+            }
 
-        if l == line {
-            println!("{} | {}{}", " ".repeat(linelen), " ".repeat(pos), "^".repeat(len));
-        } 
+            pending.push(item);
+        }
     }
-}
-
-pub fn error(source: &Rc<str>, msg: &str, pos: usize, len: usize, line: usize) {
-    report(source, msg, "error", pos, len, line);
-}
 
-pub fn runtime_error(source: &Rc<str>, msg: &str, pos: usize, len: usize, line: usize) {
-    report(source, msg, "runtime error", pos, len, line);
+    count
 }
 
-#[macro_export]
-macro_rules! token_runtime_error {
-    ($token: expr, $msg: expr) => {
-        crate::utils::runtime_error(&$token.source, $msg, $token.pos, $token.end - $token.pos, $token.line);
-        return Err(());
-    };
+pub fn call_name(token: &Token) -> Rc<str> {
+    if let TokenType::Identifier = token.type_ {
+        Rc::clone(&token.lexeme)
+    } else {
+        Rc::from("<code>")
+    }
 }
 
 pub fn is_digit(c: char) -> bool {
-    c >= '0' && c <= '9'
+    c.is_ascii_digit()
 }
 
 pub fn is_alpha(c: char) -> bool {
-    (c >= 'a' && c <= 'z') ||
-    (c >= 'A' && c <= 'Z') ||
+    c.is_ascii_lowercase() ||
+    c.is_ascii_uppercase() ||
     c == '_'
 }
 
 pub fn is_alphanumeric(c: char) -> bool {
     is_alpha(c) || is_digit(c)
+}
+
+pub fn read_char(input: &mut dyn BufRead) -> io::Result<Option<char>> {
+    let mut bytes = Vec::new();
+    loop {
+        let buf = input.fill_buf()?;
+        if buf.is_empty() {
+            break;
+        }
+
+        bytes.push(buf[0]);
+        input.consume(1);
+
+        if let Ok(decoded) = std::str::from_utf8(&bytes) {
+            return Ok(decoded.chars().next());
+        }
+
+        if bytes.len() >= 4 {
+            break;
+        }
+    }
+
+    if bytes.is_empty() {
+        Ok(None)
+    } else {
+        Err(io::Error::new(io::ErrorKind::InvalidData, "stream did not contain valid UTF-8"))
+    }
 }
\ No newline at end of file
@@ -0,0 +1,165 @@
+mod common;
+
+use common::{error_kind, error_kind_on, output, run_file, run_on};
+use stacc::{ErrorKind, Interpreter, Overflow};
+
+fn with_overflow(overflow: Overflow) -> Interpreter {
+    let mut interpreter = Interpreter::new();
+    interpreter.set_overflow(overflow);
+    interpreter
+}
+
+#[test]
+fn big_integers() {
+    assert_eq!(output("9223372036854775807 1 + $"), "9223372036854775808\n");
+    assert_eq!(output("9223372036854775807 . * $ 99999999999999999999 3 / $ 99999999999999999999 7 % $"), "85070591730234615847396907784232501249\n33333333333333333333\n1\n");
+    assert_eq!(output("9223372036854775807 1 + 1 - $"), "9223372036854775807\n");
+    assert_eq!(output("99999999999999999999 99999999999999999999 = $ 99999999999999999999 1 > $"), "1\n1\n");
+    assert_eq!(output("99999999999999999999 1.5 + $ 99999999999999999999 ~ ~ $"), "100000000000000000000\n99999999999999999999\n");
+    assert_eq!(output("18446744073709551616 18446744073709551615 & $ 0 18446744073709551616 - 1 | $"), "0\n-18446744073709551615\n");
+}
+
+#[test]
+fn big_integer_errors() {
+    assert_eq!(error_kind("99999999999999999999 \"a\" -"), ErrorKind::Type);
+}
+
+#[test]
+fn division_by_zero() {
+    assert_eq!(error_kind("1 0 /"), ErrorKind::DivisionByZero);
+    assert_eq!(error_kind("1 0 %"), ErrorKind::DivisionByZero);
+    assert_eq!(error_kind("99999999999999999999 0 /"), ErrorKind::DivisionByZero);
+    assert_eq!(output("1.0 0 / $ 1 0.0 / $"), "inf\ninf\n");
+}
+
+#[test]
+fn overflow_wraps() {
+    let mut interpreter = with_overflow(Overflow::Wrap);
+    assert_eq!(run_on(&mut interpreter, "9223372036854775807 1 + $").unwrap(), "-9223372036854775808\n");
+    assert_eq!(run_on(&mut interpreter, "4294967296 . * $ 18446744073709551617 0 + $").unwrap(), "0\n1\n");
+}
+
+#[test]
+fn overflow_saturates() {
+    let mut interpreter = with_overflow(Overflow::Saturate);
+    assert_eq!(
+        run_on(&mut interpreter, "9223372036854775807 1 + $ 0 9223372036854775807 - 2 - $").unwrap(),
+        "9223372036854775807\n-9223372036854775808\n"
+    );
+}
+
+#[test]
+fn overflow_errors() {
+    let mut interpreter = with_overflow(Overflow::Error);
+    assert_eq!(error_kind_on(&mut interpreter, "9223372036854775807 1 +"), ErrorKind::Overflow);
+    assert_eq!(error_kind_on(&mut interpreter, "4294967296 . *"), ErrorKind::Overflow);
+}
+
+#[test]
+fn overflow_policy_from_program() {
+    assert_eq!(output("\"wrap\" overflow 9223372036854775807 1 + $"), "-9223372036854775808\n");
+    assert_eq!(error_kind("\"nope\" overflow"), ErrorKind::OutOfRange);
+}
+
+#[test]
+fn overflow_policy_from_command_line() {
+    let output = run_file("9223372036854775807 1 + $", &["--overflow=wrap"], "");
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "-9223372036854775808\n");
+    assert_eq!(run_file("9223372036854775807 1 +", &["--overflow=error"], "").status.code(), Some(70));
+    assert!(!run_file("1", &["--overflow=nope"], "").status.success());
+}
+
+#[test]
+fn bitwise_operations() {
+    assert_eq!(output("12 10 xor $ 1 4 shl $ -16 2 shr $ -1 popcnt $"), "6\n16\n-4\n64\n");
+    assert_eq!(output("-16 2 ushr $ 16 2 ushr $ 255 popcnt $ 0 popcnt $"), "4611686018427387900\n4\n8\n0\n");
+    assert_eq!(output("99999999999999999999 99999999999999999998 xor $"), "1\n");
+    assert_eq!(error_kind("1.5 1 shl"), ErrorKind::Type);
+}
+
+#[test]
+fn rotations() {
+    assert_eq!(output("1 1 rotr $ -9223372036854775808 1 rotl $ 1 -1 rotl $ 3 65 rotl $"), "-9223372036854775808\n1\n-9223372036854775808\n6\n");
+    assert_eq!(output("6 1 rotr $ 6 -65 rotr $"), "3\n12\n");
+}
+
+#[test]
+fn shift_amount_out_of_range() {
+    assert_eq!(error_kind("1 64 ushr"), ErrorKind::OutOfRange);
+    assert_eq!(error_kind("1 -1 shr"), ErrorKind::OutOfRange);
+    assert_eq!(error_kind("1 1.5 rotl"), ErrorKind::Type);
+}
+
+#[test]
+fn shifts_on_big_integers() {
+    assert_eq!(output("9223372036854775808 1 shl $ 1 63 shl $ -9223372036854775809 1 shr $ 5 100 shr $"), "18446744073709551616\n9223372036854775808\n-4611686018427387905\n0\n");
+    assert_eq!(output("18446744073709551615 popcnt $"), "64\n");
+    assert_eq!(error_kind("9223372036854775808 1 ushr"), ErrorKind::Overflow);
+    assert_eq!(error_kind("1 2000000 shl"), ErrorKind::IntLimit);
+    assert_eq!(error_kind("1 -1 shl"), ErrorKind::OutOfRange);
+
+    let mut interpreter = with_overflow(Overflow::Wrap);
+    assert_eq!(run_on(&mut interpreter, "1 63 shl $ 3 200 shl $ 9223372036854775808 1 ushr $").unwrap(), "-9223372036854775808\n0\n4611686018427387904\n");
+
+    let mut interpreter = with_overflow(Overflow::Saturate);
+    assert_eq!(run_on(&mut interpreter, "3 200 shl $ -18446744073709551616 popcnt $").unwrap(), "9223372036854775807\n1\n");
+}
+
+#[test]
+fn math_operations() {
+    assert_eq!(output("16 sqrt $ 2 10 pow $ 2 -1 pow $ -2.5 floor $ -7 abs $ 3 1.5 min $"), "4\n1024\n0.5\n-3\n7\n1.5\n");
+    assert_eq!(output("-2.5 ceil $ 2.5 round $ -2.5 round $ 7 ceil $ -2.5 abs $ 3 1.5 max $ 2 7 max $"), "-2\n3\n-3\n7\n2.5\n3\n7\n");
+    assert_eq!(error_kind("\"a\" sqrt"), ErrorKind::Type);
+    assert_eq!(error_kind("1 \"a\" max"), ErrorKind::Type);
+}
+
+#[test]
+fn transcendental_functions() {
+    assert_eq!(output("0 exp $ 1 log $ 0 sin $ 0 cos $ 0 tan $ -1 sqrt $"), "1\n0\n0\n1\n0\nNaN\n");
+    assert_eq!(output("pi $ euler $ euler log $ pi 2 / sin $"), "3.141592653589793\n2.718281828459045\n1\n1\n");
+    assert_eq!(output("{ 2 * } \"e\" : 3 e $"), "6\n");
+}
+
+#[test]
+fn pow_respects_overflow_policy() {
+    assert_eq!(output("2 64 pow $ -3 3 pow $ 1 4000000000 pow $"), "18446744073709551616\n-27\n1\n");
+    assert_eq!(error_kind("2 100000000 pow"), ErrorKind::IntLimit);
+
+    let mut interpreter = with_overflow(Overflow::Wrap);
+    assert_eq!(run_on(&mut interpreter, "2 100000000 pow $ 3 41 pow $").unwrap(), format!("0\n{}\n", 3i64.wrapping_pow(41)));
+    assert_eq!(run_on(&mut interpreter, "18446744073709551619 3 pow $").unwrap(), "27\n");
+
+    let mut interpreter = with_overflow(Overflow::Saturate);
+    assert_eq!(run_on(&mut interpreter, "2 100000000 pow $ -2 100000001 pow $").unwrap(), "9223372036854775807\n-9223372036854775808\n");
+    assert_eq!(run_on(&mut interpreter, "99999999999999999999 0 pow $").unwrap(), "1\n");
+
+    let mut interpreter = with_overflow(Overflow::Error);
+    assert_eq!(error_kind_on(&mut interpreter, "2 100000000 pow"), ErrorKind::Overflow);
+    assert_eq!(error_kind_on(&mut interpreter, "99999999999999999999 2 pow"), ErrorKind::Overflow);
+}
+
+#[test]
+fn pow_with_large_exponents() {
+    assert_eq!(error_kind("2 99999999999999999999 pow"), ErrorKind::IntLimit);
+    assert_eq!(error_kind("2 5000000000 pow"), ErrorKind::IntLimit);
+    assert_eq!(
+        output("1 5000000000 pow $ -1 5000000001 pow $ 0 99999999999999999999 pow $ 0 0 pow $ -1 99999999999999999998 pow $"),
+        "1\n-1\n0\n1\n1\n"
+    );
+
+    let mut interpreter = with_overflow(Overflow::Wrap);
+    assert_eq!(
+        run_on(&mut interpreter, "2 5000000000 pow $ 3 99999999999999999999 pow $").unwrap(),
+        "0\n4844446824547396267\n"
+    );
+
+    let mut interpreter = with_overflow(Overflow::Saturate);
+    assert_eq!(
+        run_on(&mut interpreter, "-2 5000000001 pow $ 3 99999999999999999999 pow $ 1 5000000000 pow $").unwrap(),
+        "-9223372036854775808\n9223372036854775807\n1\n"
+    );
+
+    let mut interpreter = with_overflow(Overflow::Error);
+    assert_eq!(error_kind_on(&mut interpreter, "2 5000000000 pow"), ErrorKind::Overflow);
+    assert_eq!(run_on(&mut interpreter, "-1 99999999999999999999 pow $").unwrap(), "-1\n");
+}
@@ -0,0 +1,40 @@
+mod common;
+
+use common::{messages, run_file};
+
+#[test]
+fn exit_statuses() {
+    assert_eq!(run_file("1 $", &[], "").status.code(), Some(0));
+    assert_eq!(run_file("1 $ \"open", &[], "").status.code(), Some(65));
+    assert_eq!(run_file("1 +", &[], "").status.code(), Some(70));
+    assert_eq!(run_file("3 exit 4 exit", &[], "").status.code(), Some(3));
+}
+
+#[test]
+fn syntax_errors_prevent_execution() {
+    let output = run_file("1 $ \"open", &[], "");
+    assert!(String::from_utf8_lossy(&output.stdout).lines().all(|line| line != "1"));
+}
+
+#[test]
+fn exit_status_out_of_range() {
+    assert_eq!(run_file("\"a\" exit", &[], "").status.code(), Some(70));
+    assert_eq!(run_file("99999999999 exit", &[], "").status.code(), Some(70));
+}
+
+#[test]
+fn errors_report_location_and_call_chain() {
+    let report = messages("{ 1 + } \"f\" :\n{ f } \"g\" :\ng");
+    assert!(report.starts_with("runtime error ("));
+    assert!(report.contains(", line 1, pos 4): Popped empty primary stack\n"));
+    assert!(report.contains("1 | { 1 + } \"f\" :\n  |     ^\n"));
+    assert!(report.contains("\n  called from f ("));
+    assert!(report.contains("\n  called from g ("));
+}
+
+#[test]
+fn errors_go_to_standard_error() {
+    let output = run_file("1 $ 2 +", &[], "");
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "1\n");
+    assert!(String::from_utf8(output.stderr).unwrap().starts_with("runtime error"));
+}
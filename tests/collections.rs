@@ -0,0 +1,57 @@
+mod common;
+
+use common::{error_kind, output};
+use stacc::ErrorKind;
+
+#[test]
+fn lists() {
+    assert_eq!(output("1 2.5 \"three\" 3 pack . $ len $"), "[1, 2.5, \"three\"]\n3\n");
+    assert_eq!(output("1 2 3 3 pack . 1 get $ 4 append unpack 4 pack $"), "2\n[1, 2, 3, 4]\n");
+    assert_eq!(output("1 1 pack 2 1 pack + 0 pack 0 pack 2 pack + $"), "[1, 2, [], []]\n");
+    assert_eq!(output("\"hello\" len $ 0 pack { \"no\" $ } ? 0 pack 1 pack { \"yes\" $ } ?"), "5\nyes\n");
+}
+
+#[test]
+fn list_comparison() {
+    assert_eq!(output("1 2 2 pack 1 2 2 pack = $ 1 2 2 pack 1 3 2 pack < $ 1 1 pack 1 2 2 pack > $"), "1\n1\n0\n");
+}
+
+#[test]
+fn list_errors() {
+    assert_eq!(error_kind("1 2 3 pack"), ErrorKind::StackUnderflow);
+    assert_eq!(error_kind("0 1 - pack"), ErrorKind::OutOfRange);
+    assert_eq!(error_kind("0 pack 0 get"), ErrorKind::OutOfRange);
+    assert_eq!(error_kind("1 unpack"), ErrorKind::Type);
+}
+
+#[test]
+fn list_set() {
+    assert_eq!(output("1 2 3 3 pack 1 \"two\" set $"), "[1, \"two\", 3]\n");
+    assert_eq!(error_kind("1 1 pack 1 0 set"), ErrorKind::OutOfRange);
+}
+
+#[test]
+fn maps() {
+    assert_eq!(output("map \"two\" 2 set 1 \"one\" set . $ len $"), "{1: \"one\", \"two\": 2}\n2\n");
+    assert_eq!(output("map 1 \"one\" set \"two\" 2 set . keys $ . values $ \"two\" get $"), "[1, \"two\"]\n[\"one\", 2]\n2\n");
+    assert_eq!(output("map 1 1 set 1 2 set $"), "{1: 2}\n");
+}
+
+#[test]
+fn map_del_and_has() {
+    assert_eq!(output("map 1 1 set 2 2 set 1 del . 1 has $ . 2 has $ 3 del $"), "0\n1\n{2: 2}\n");
+}
+
+#[test]
+fn map_comparison_and_truthiness() {
+    assert_eq!(output("map 1 1 set \"a\" 2 set map \"a\" 2 set 1 1 set = $ map 1 1 set map 1 2 set = $"), "1\n0\n");
+    assert_eq!(output("map { \"no\" $ } ? map 1 1 set { \"yes\" $ } ?"), "yes\n");
+}
+
+#[test]
+fn map_errors() {
+    assert_eq!(error_kind("map 1 get"), ErrorKind::MissingKey);
+    assert_eq!(error_kind("map 1.5 1 set"), ErrorKind::Type);
+    assert_eq!(error_kind("map 0 pack has"), ErrorKind::Type);
+    assert_eq!(error_kind("1 keys"), ErrorKind::Type);
+}
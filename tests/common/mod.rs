@@ -0,0 +1,80 @@
+#![allow(dead_code)]
+
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Output, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use stacc::{compile, ErrorKind, Interpreter, Interrupt, SharedBuffer};
+
+static FILES: AtomicUsize = AtomicUsize::new(0);
+
+pub fn stacc(args: &[&str], input: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_stacc"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("failed to start stacc");
+
+    child.stdin.take().unwrap().write_all(input.as_bytes()).unwrap();
+    child.wait_with_output().unwrap()
+}
+
+pub fn source_file(source: &str) -> PathBuf {
+    let id = FILES.fetch_add(1, Ordering::Relaxed);
+    let path = std::env::temp_dir().join(format!("stacc-test-{}-{}.scc", std::process::id(), id));
+    std::fs::write(&path, source).unwrap();
+    path
+}
+
+pub fn run_file(source: &str, args: &[&str], input: &str) -> Output {
+    let path = source_file(source);
+    let mut all_args = args.to_vec();
+    all_args.push(path.to_str().unwrap());
+
+    let output = stacc(&all_args, input);
+    std::fs::remove_file(path).unwrap();
+    output
+}
+
+pub fn transcript(output: &Output) -> String {
+    format!("{}{}", String::from_utf8_lossy(&output.stdout), String::from_utf8_lossy(&output.stderr))
+}
+
+pub fn messages(source: &str) -> String {
+    transcript(&run_file(source, &[], ""))
+}
+
+pub fn run_on(interpreter: &mut Interpreter, source: &str) -> Result<String, Interrupt> {
+    let output = SharedBuffer::new();
+    interpreter.set_output(output.clone());
+
+    let code = compile(source, "<test>").map_err(|mut errors| Interrupt::Error(Box::new(errors.remove(0))))?;
+    interpreter.run(&code)?;
+    Ok(output.contents())
+}
+
+pub fn run(source: &str) -> Result<String, Interrupt> {
+    run_on(&mut Interpreter::new(), source)
+}
+
+pub fn output(source: &str) -> String {
+    match run(source) {
+        Ok(output) => output,
+        Err(Interrupt::Error(error)) => panic!("unexpected error: {}", error),
+        Err(other) => panic!("unexpected interrupt: {:?}", other),
+    }
+}
+
+pub fn error_kind_on(interpreter: &mut Interpreter, source: &str) -> ErrorKind {
+    match run_on(interpreter, source) {
+        Err(Interrupt::Error(error)) => error.kind,
+        other => panic!("expected an error, got {:?}", other),
+    }
+}
+
+pub fn error_kind(source: &str) -> ErrorKind {
+    error_kind_on(&mut Interpreter::new(), source)
+}
@@ -0,0 +1,63 @@
+mod common;
+
+use common::run_file;
+
+fn debug(source: &str, commands: &str) -> (String, String) {
+    let output = run_file(source, &["--debug"], commands);
+    (String::from_utf8(output.stdout).unwrap(), String::from_utf8(output.stderr).unwrap())
+}
+
+#[test]
+fn steps_through_tokens() {
+    let (stdout, stderr) = debug("1 2 + $", "s\ns\np\nc\n");
+    assert_eq!(stdout, "3\n");
+    assert!(stderr.contains("(line 1, pos 0) in <main>, depth 0: 1\n"));
+    assert!(stderr.contains("(line 1, pos 4) in <main>, depth 0: +\n"));
+    assert!(stderr.contains(" FIRST STACK: [1, 2]\nSECOND STACK: []\n"));
+}
+
+#[test]
+fn steps_over_and_into_calls() {
+    let source = "{ 1 + } \"inc\" : 1 inc $";
+    let (_, stderr) = debug(source, "n\nn\nn\nn\nn\nc\n");
+    assert!(!stderr.contains("in inc"));
+
+    let (_, stderr) = debug(source, "s\ns\ns\ns\ns\nf\nc\n");
+    assert!(stderr.contains("in inc, depth 1: 1\n"));
+    assert!(stderr.contains("in <main>, depth 0: $\n"));
+}
+
+#[test]
+fn breakpoints() {
+    let (stdout, stderr) = debug("{ 1 + } \"inc\" : 1 inc inc $", "b fn inc\nbl\nc\np\nc\np\nc\n");
+    assert_eq!(stdout, "3\n");
+    assert!(stderr.contains("breakpoint 0: function inc\n(debug) 0: function inc\n"));
+    assert!(stderr.contains(" FIRST STACK: [1]\n"));
+    assert!(stderr.contains(" FIRST STACK: [2]\n"));
+
+    let (_, stderr) = debug("0 [loop] 1 + . 3 < \"loop\" ?\n$", "b label loop\nc\nd 0\nb line 2\nc\np\nc\n");
+    assert!(stderr.contains("breakpoint 0: line 2\n"));
+    assert!(stderr.contains(" FIRST STACK: [3]\n"));
+}
+
+#[test]
+fn breakpoints_at_the_end_and_in_loops() {
+    let (_, stderr) = debug("0 [loop] . 2 > \"end\" ? 1 + \"loop\" ^ [end]", "b label end\nc\np\nc\n");
+    assert!(stderr.contains("(line 1) in <main>, depth 0: end of code\n"));
+    assert!(stderr.contains(" FIRST STACK: [3]\n"));
+
+    let (stdout, stderr) = debug("0 [loop] 1 + . 3 < \"loop\" ?\n$", "b line 1\nc\np\nc\np\nc\n");
+    assert_eq!(stdout, "3\n");
+    assert!(stderr.contains(" FIRST STACK: [1]\n"));
+    assert!(stderr.contains(" FIRST STACK: [2]\n"));
+}
+
+#[test]
+fn edits_stacks_and_quits() {
+    let (stdout, _) = debug("$", "push st 1 \"two\"\npop st\npush nd 3\nclear nd\nc\n");
+    assert_eq!(stdout, "1\n");
+
+    let output = run_file("1 $", &["--debug"], "q\n");
+    assert!(output.stdout.is_empty());
+    assert_eq!(output.status.code(), Some(0));
+}
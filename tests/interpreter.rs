@@ -0,0 +1,193 @@
+mod common;
+
+use std::cell::RefCell;
+use std::io::Cursor;
+use std::rc::Rc;
+
+use common::{error_kind, error_kind_on, messages, output, run_file, run_on};
+use stacc::{compile, ErrorKind, Hook, Interpreter, Interrupt, Object, StaccError, Step};
+
+#[test]
+fn code_objects_run_once() {
+    assert_eq!(output("{ 1 $ } ^ 2 $"), "1\n2\n");
+    assert_eq!(output("0 { \"no\" $ } ? 1 { \"yes\" $ } ? \"end\" $"), "yes\nend\n");
+}
+
+#[test]
+fn input_operations() {
+    let output = run_file("readline $ readchar $ readall $ readall $ readline $", &[], "first\nsecond");
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "first\ns\necond\n0\n0\n");
+}
+
+#[test]
+fn builtin_names_are_reserved() {
+    assert!(messages("{ 1 } \"readline\" :").contains("Cannot redefine built-in operation \"readline\""));
+}
+
+#[test]
+fn host_pushes_and_pops() {
+    let mut interpreter = Interpreter::new();
+    interpreter.push(Object::Int(20));
+    interpreter.push(Object::Int(22));
+    interpreter.run(&compile("+ . ,", "<test>").unwrap()).unwrap();
+
+    assert!(matches!(interpreter.secondary(), [Object::Int(42)]));
+    assert!(matches!(interpreter.pop(), Some(Object::Int(42))));
+    assert!(interpreter.primary().is_empty());
+    assert!(interpreter.pop().is_none());
+}
+
+#[test]
+fn state_persists_between_runs() {
+    let mut interpreter = Interpreter::new();
+    interpreter.run(&compile("{ 2 * } \"double\" : 21", "<test>").unwrap()).unwrap();
+    interpreter.run(&compile("double", "<test>").unwrap()).unwrap();
+
+    assert!(matches!(interpreter.primary(), [Object::Int(42)]));
+}
+
+#[test]
+fn compile_reports_every_error() {
+    let Err(errors) = compile("\"\\q\" 1 \"open", "<test>") else {
+        panic!("expected syntax errors");
+    };
+
+    assert_eq!(errors.len(), 2);
+    assert!(errors.iter().all(|error| error.kind == ErrorKind::Syntax));
+}
+
+#[test]
+fn runtime_errors_and_exit() {
+    assert_eq!(error_kind("1 +"), ErrorKind::StackUnderflow);
+    assert_eq!(error_kind("\"a\" 1 -"), ErrorKind::Type);
+    assert_eq!(error_kind("undefined"), ErrorKind::UndefinedFunction);
+
+    let mut interpreter = Interpreter::new();
+    let result = interpreter.run(&compile("3 exit", "<test>").unwrap());
+    assert!(matches!(result, Err(Interrupt::Exit(3))));
+}
+
+fn with_double() -> Interpreter {
+    let mut interpreter = Interpreter::new();
+    interpreter.register("double", |interpreter| {
+        match interpreter.pop() {
+            Some(Object::Int(x)) => {
+                interpreter.push(Object::Int(x * 2));
+                Ok(())
+            }
+            _ => Err(StaccError::native(ErrorKind::Type, "Expecting int to double"))
+        }
+    }).unwrap();
+
+    interpreter
+}
+
+#[test]
+fn native_functions() {
+    let mut interpreter = with_double();
+    interpreter.run(&compile("21 double { double double } \"quadruple\" : 5 quadruple", "<test>").unwrap()).unwrap();
+    assert!(matches!(interpreter.primary(), [Object::Int(42), Object::Int(20)]));
+
+    assert_eq!(error_kind_on(&mut interpreter, "\"a\" double"), ErrorKind::Type);
+}
+
+#[test]
+fn native_errors_point_at_the_call() {
+    let mut interpreter = with_double();
+    let Err(Interrupt::Error(error)) = interpreter.run(&compile("1 \"a\" double", "<test>").unwrap()) else {
+        panic!("expected an error");
+    };
+
+    assert_eq!(error.message, "Expecting int to double");
+    assert_eq!((error.span.line, error.span.pos), (0, 6));
+}
+
+#[test]
+fn native_functions_can_be_replaced() {
+    let mut interpreter = with_double();
+    interpreter.run(&compile("{ 3 * } \"double\" : 2 double", "<test>").unwrap()).unwrap();
+    assert!(matches!(interpreter.primary(), [Object::Int(6)]));
+}
+
+#[test]
+fn native_function_names_are_checked() {
+    let mut interpreter = Interpreter::new();
+    assert_eq!(interpreter.register("swap", |_| Ok(())).unwrap_err().kind, ErrorKind::ReservedName);
+    assert_eq!(interpreter.register("", |_| Ok(())).unwrap_err().kind, ErrorKind::Syntax);
+    assert_eq!(interpreter.register("2x", |_| Ok(())).unwrap_err().kind, ErrorKind::Syntax);
+    assert_eq!(interpreter.register("my fn", |_| Ok(())).unwrap_err().kind, ErrorKind::Syntax);
+    assert!(interpreter.register("_helper2", |_| Ok(())).is_ok());
+    assert_eq!(error_kind_on(&mut interpreter, "swap"), ErrorKind::StackUnderflow);
+}
+
+#[test]
+fn redirected_streams() {
+    let mut interpreter = Interpreter::new();
+    interpreter.set_input(Cursor::new("world\nagain"));
+    assert_eq!(run_on(&mut interpreter, "\"Hello, \" readline + $").unwrap(), "Hello, world\n");
+    assert_eq!(run_on(&mut interpreter, "readall $ readline $").unwrap(), "again\n0\n");
+}
+
+struct Recorder(Rc<RefCell<Vec<String>>>);
+
+impl Hook for Recorder {
+    fn before_token(&mut self, interpreter: &mut Interpreter, step: &Step) -> Result<(), Interrupt> {
+        let function = interpreter.frames().last().map(|f| f.to_string()).unwrap_or_default();
+        self.0.borrow_mut().push(format!("{} {} {}", step.token.lexeme, step.depth, function));
+        Ok(())
+    }
+}
+
+#[test]
+fn hooks_see_every_token() {
+    let steps = Rc::new(RefCell::new(Vec::new()));
+    let mut interpreter = Interpreter::new();
+    interpreter.set_hook(Recorder(steps.clone()));
+    run_on(&mut interpreter, "{ 1 + } \"inc\" : 1 inc").unwrap();
+
+    assert_eq!(*steps.borrow(), [
+        "{ 0 ", "\"inc\" 0 ", ": 0 ", "1 0 ", "inc 0 ", "1 1 inc", "+ 1 inc", " 1 inc", " 0 "
+    ]);
+}
+
+#[test]
+fn return_from_function() {
+    assert_eq!(output("{ 1 $ 1 { return } ? 2 $ } \"f\" : f 3 $"), "1\n3\n");
+    assert_eq!(output("{ 1 $ return 2 $ } \"f\" : { f 3 $ } \"g\" : g 4 $"), "1\n3\n4\n");
+    assert_eq!(output("1 $ return 2 $"), "1\n");
+}
+
+#[test]
+fn halt_stops_the_program() {
+    assert_eq!(output("1 $ halt 2 $"), "1\n");
+    assert_eq!(output("{ { 1 $ halt } ^ 2 $ } \"f\" : f 3 $"), "1\n");
+    assert_eq!(run_file("{ halt } \"f\" : f 1 exit", &[], "").status.code(), Some(0));
+}
+
+#[test]
+fn try_restores_stacks() {
+    assert_eq!(
+        output("1 , 2 { 3 4 ; ; ; ; } { 2 pack $ depth $ nddepth $ } try"),
+        "[\"Popped empty secondary stack\", \"StackUnderflow\"]\n1\n1\n"
+    );
+    assert_eq!(output("{ 1 2 } { 3 } try 2 pack $"), "[1, 2]\n");
+}
+
+#[test]
+fn try_catches_syntax_errors() {
+    assert_eq!(output("\"\\\"open\" { ~^ } { $ drop } try depth $"), "Syntax\n1\n");
+}
+
+#[test]
+fn try_does_not_catch_exit_halt_or_return() {
+    let mut interpreter = Interpreter::new();
+    assert!(matches!(run_on(&mut interpreter, "{ 4 exit } { \"caught\" $ } try"), Err(Interrupt::Exit(4))));
+    assert_eq!(output("{ halt } { \"caught\" $ } try 1 $"), "");
+    assert_eq!(output("{ { return } { \"caught\" $ } try 1 $ } \"f\" : f 2 $"), "2\n");
+}
+
+#[test]
+fn try_errors() {
+    assert_eq!(error_kind("1 { } try"), ErrorKind::Type);
+    assert_eq!(error_kind("{ 1 + } { drop drop drop } try"), ErrorKind::StackUnderflow);
+}
@@ -0,0 +1,104 @@
+mod common;
+
+use common::{error_kind_on, run_on};
+use stacc::{ErrorKind, Interpreter, Limits};
+
+fn limited(limits: Limits) -> Interpreter {
+    let mut interpreter = Interpreter::new();
+    interpreter.set_limits(limits);
+    interpreter
+}
+
+#[test]
+fn step_limit() {
+    let mut interpreter = limited(Limits { max_steps: Some(100), ..Limits::default() });
+    assert_eq!(error_kind_on(&mut interpreter, "[l] \"l\" ^"), ErrorKind::StepLimit);
+}
+
+#[test]
+fn depth_limit() {
+    let mut interpreter = limited(Limits { max_depth: Some(16), ..Limits::default() });
+    assert_eq!(error_kind_on(&mut interpreter, "{ f } \"f\" : f"), ErrorKind::DepthLimit);
+}
+
+#[test]
+fn stack_limit() {
+    let mut interpreter = limited(Limits { max_stack: Some(8), ..Limits::default() });
+    assert_eq!(error_kind_on(&mut interpreter, "[l] 1 \"l\" ^"), ErrorKind::StackLimit);
+
+    let mut interpreter = limited(Limits { max_stack: Some(8), ..Limits::default() });
+    assert_eq!(error_kind_on(&mut interpreter, "[m] 1 , \"m\" ^"), ErrorKind::StackLimit);
+}
+
+#[test]
+fn move_keeps_value_when_destination_is_full() {
+    let mut interpreter = limited(Limits { max_stack: Some(3), ..Limits::default() });
+    let source = "\"q\" newstack 1 \"st\" \"q\" move 2 \"st\" \"q\" move 3 \"st\" \"q\" move 4 \"st\" \"q\" move";
+    assert_eq!(error_kind_on(&mut interpreter, source), ErrorKind::StackLimit);
+    assert_eq!(run_on(&mut interpreter, "depth $ $").unwrap(), "1\n4\n");
+}
+
+#[test]
+fn string_limit() {
+    let mut interpreter = limited(Limits { max_string: Some(64), ..Limits::default() });
+    assert_eq!(error_kind_on(&mut interpreter, "\"ab\" [l] . + \"l\" ^"), ErrorKind::StringLimit);
+}
+
+#[test]
+fn limits_are_not_caught_by_try() {
+    let mut interpreter = limited(Limits { max_steps: Some(100), ..Limits::default() });
+    assert_eq!(error_kind_on(&mut interpreter, "{ [l] \"l\" ^ } { \"caught\" $ } try"), ErrorKind::StepLimit);
+}
+
+#[test]
+fn steps_reset_between_runs() {
+    let mut interpreter = limited(Limits { max_steps: Some(10), ..Limits::default() });
+    for _ in 0..3 {
+        assert_eq!(run_on(&mut interpreter, "1 2 + $").unwrap(), "3\n");
+    }
+}
+
+#[test]
+fn int_limit() {
+    let mut interpreter = limited(Limits { max_steps: Some(100), ..Limits::default() });
+    assert_eq!(error_kind_on(&mut interpreter, "3 [l] . * \"l\" ^"), ErrorKind::IntLimit);
+
+    let mut interpreter = limited(Limits { max_int_bits: Some(64), ..Limits::default() });
+    assert_eq!(run_on(&mut interpreter, "9223372036854775807 1 + $").unwrap(), "9223372036854775808\n");
+    assert_eq!(error_kind_on(&mut interpreter, "18446744073709551615 1 +"), ErrorKind::IntLimit);
+}
+
+#[test]
+fn item_limit() {
+    let mut interpreter = limited(Limits { max_steps: Some(300), ..Limits::default() });
+    assert_eq!(error_kind_on(&mut interpreter, "1 1 pack [l] . + \"l\" ^"), ErrorKind::ItemLimit);
+
+    let mut interpreter = limited(Limits { max_items: Some(3), ..Limits::default() });
+    assert_eq!(run_on(&mut interpreter, "1 2 3 3 pack len $").unwrap(), "3\n");
+    assert_eq!(error_kind_on(&mut interpreter, "1 2 3 3 pack 4 append"), ErrorKind::ItemLimit);
+    assert_eq!(error_kind_on(&mut interpreter, "map 1 1 set 2 2 set 3 3 set 4 4 set"), ErrorKind::ItemLimit);
+}
+
+#[test]
+fn item_limit_counts_nested_items() {
+    let mut interpreter = limited(Limits { max_steps: Some(300), ..Limits::default() });
+    assert_eq!(
+        error_kind_on(&mut interpreter, "1 25 [l] , . 2 pack ; 1 - . \"l\" ? drop $"),
+        ErrorKind::ItemLimit
+    );
+
+    let mut interpreter = limited(Limits { max_items: Some(4), ..Limits::default() });
+    assert_eq!(run_on(&mut interpreter, "1 2 2 pack 3 2 pack len $").unwrap(), "2\n");
+    assert_eq!(error_kind_on(&mut interpreter, "1 2 2 pack 3 4 2 pack 2 pack"), ErrorKind::ItemLimit);
+    assert_eq!(error_kind_on(&mut interpreter, "map 1 1 2 3 3 pack set 2 2 set"), ErrorKind::ItemLimit);
+}
+
+#[test]
+fn code_limit() {
+    let mut interpreter = limited(Limits { max_steps: Some(300), ..Limits::default() });
+    assert_eq!(error_kind_on(&mut interpreter, "{ 1 } [l] . + \"l\" ^"), ErrorKind::CodeLimit);
+
+    let mut interpreter = limited(Limits { max_code: Some(4), ..Limits::default() });
+    assert_eq!(run_on(&mut interpreter, "{ 1 $ } { 2 } + ^ $").unwrap(), "1\n2\n");
+    assert_eq!(error_kind_on(&mut interpreter, "{ 1 2 } { 3 4 } +"), ErrorKind::CodeLimit);
+}
@@ -0,0 +1,20 @@
+mod common;
+
+use common::{stacc, transcript};
+
+#[test]
+fn state_persists_between_lines() {
+    let output = transcript(&stacc(&[], "1 2\n+ ,\n{ 1 + } \"inc\" :\n; inc $\n"));
+    assert!(output.contains(" FIRST STACK: [1, 2]\nSECOND STACK: []\n"));
+    assert!(output.contains(" FIRST STACK: []\nSECOND STACK: [3]\n"));
+    assert!(output.contains("4\n"));
+}
+
+#[test]
+fn errors_do_not_end_the_session() {
+    let output = transcript(&stacc(&[], "1 +\n\"open\n[\n2 $\n"));
+    assert!(output.contains("runtime error"));
+    assert!(output.contains("error (<stdin>, line 1"));
+    assert!(output.contains("Unterminated label"));
+    assert!(output.contains("2\n FIRST STACK: []"));
+}
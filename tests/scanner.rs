@@ -0,0 +1,82 @@
+mod common;
+
+use common::{error_kind, messages, output};
+use stacc::{compile, ErrorKind};
+
+#[test]
+fn string_escapes() {
+    assert_eq!(output(r#""a\tb\n" $ "\"q\" \\ \u{e9}" $"#), "a\tb\n\n\"q\" \\ \u{e9}\n");
+    assert_eq!(output(r"`raw\n` $"), "raw\\n\n");
+}
+
+#[test]
+fn invalid_escapes() {
+    assert!(messages(r#""\q" $"#).contains("Invalid escape sequence"));
+    assert!(messages(r#""\u{110000}" $"#).contains("Invalid unicode escape"));
+    assert!(messages(r#""\u{e9" $"#).contains("Unterminated unicode escape"));
+}
+
+#[test]
+fn radix_literals() {
+    assert_eq!(output("0xff $ 0XFF $ 0b1010 $ 0o17 $"), "255\n255\n10\n15\n");
+    assert_eq!(output("0xffffffffffffffffffff $"), "1208925819614629174706175\n");
+    assert!(compile("0x", "<test>").is_err());
+
+    for source in ["0b102", "0xffg", "0o8", "12abc", "1.5x", "1e", "-5x"] {
+        assert!(compile(source, "<test>").is_err(), "{} should not compile", source);
+    }
+}
+
+#[test]
+fn digit_separators() {
+    assert_eq!(output("1_000_000 $ 0xffff_ffff $ 1_0.2_5 $"), "1000000\n4294967295\n10.25\n");
+
+    for source in ["1__0", "1_", "0x_1_", "1._5", "0x_ $", "0b_", "1e5_"] {
+        assert!(compile(source, "<test>").is_err(), "{} should not compile", source);
+    }
+}
+
+#[test]
+fn scientific_notation() {
+    assert_eq!(output("2.5e3 $ 1e-2 $ 6E+2 $"), "2500\n0.01\n600\n");
+    assert!(compile("1e999", "<test>").is_err());
+}
+
+#[test]
+fn negative_literals() {
+    assert_eq!(output("-5 $ -0x10 $ -1.5 $ {-3} ^ $"), "-5\n-16\n-1.5\n-3\n");
+    assert_eq!(output("5 -3 2 pack $"), "[5, -3]\n");
+    assert_eq!(output("5 3- $ 5 3 - $"), "2\n2\n");
+    assert_eq!(output("-9223372036854775808 $ -9223372036854775809 $"), "-9223372036854775808\n-9223372036854775809\n");
+    assert_eq!(output("1 (c)-1 2 pack $ 1 \\ c\n-1 2 pack $"), "[1, -1]\n[1, -1]\n");
+    assert_eq!(output("[l]-1 $"), "-1\n");
+    assert_eq!(output("2 3-1 2 pack $"), "[-1, 1]\n");
+}
+
+#[test]
+fn comments_produce_no_tokens() {
+    assert_eq!(output("\\ a line comment\n1 ( a ( nested ) block\ncomment ) 2 + $"), "3\n");
+    assert_eq!(output("3 ( skipped ) ^ 3 $ 4 $"), "4\n");
+    assert!(messages("1 ( open ( nested ) $").contains("Unterminated comment"));
+}
+
+#[test]
+fn nested_code_blocks() {
+    assert_eq!(output("{ { \"}\" $ } \"g\" : g [x] } \"f\" : f"), "}\n");
+    assert_eq!(output("{ 1 $ { 2 $ { 3 $ } \"h\" : h } \"g\" : g } \"f\" : f"), "1\n2\n3\n");
+    assert!(messages("{ { }").contains("Unterminated code block"));
+}
+
+#[test]
+fn unterminated_label() {
+    assert_eq!(error_kind("["), ErrorKind::Syntax);
+    assert_eq!(error_kind("1 [abc"), ErrorKind::Syntax);
+    assert_eq!(output("{ \"[\" ~ } { $ $ } try"), "Syntax\nUnterminated label\n");
+}
+
+#[test]
+fn non_ascii_source() {
+    assert_eq!(output("\"é\" $ `日本` $ ( コメント ) \\ ü\n\"ok\" $"), "é\n日本\nok\n");
+    assert_eq!(output("[ラベル] \"ラベル\" \"é\" . len $ $"), "1\né\n");
+    assert_eq!(error_kind("\"é\" é"), ErrorKind::Syntax);
+}
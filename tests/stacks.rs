@@ -0,0 +1,58 @@
+mod common;
+
+use common::{error_kind, output};
+use stacc::ErrorKind;
+
+#[test]
+fn primary_stack_primitives() {
+    assert_eq!(output("1 2 swap 2 pack $ 1 2 over 3 pack $ 1 2 3 rot 3 pack $"), "[2, 1]\n[1, 2, 1]\n[2, 3, 1]\n");
+    assert_eq!(output("1 2 3 2 pick 4 pack $ 1 2 3 2 roll 3 pack $ 1 2 drop depth $ $"), "[1, 2, 3, 1]\n[2, 3, 1]\n1\n1\n");
+    assert_eq!(output("1 2 0 pick 3 pack $ 1 2 0 roll 2 pack $"), "[1, 2, 2]\n[1, 2]\n");
+}
+
+#[test]
+fn secondary_stack_primitives() {
+    assert_eq!(output("1 , 2 , ndswap # 2 pack $"), "[2, 1]\n");
+    assert_eq!(output("1 , 2 , ndover # 3 pack $"), "[1, 2, 1]\n");
+    assert_eq!(output("1 , 2 , 3 , ndrot # 3 pack $"), "[2, 3, 1]\n");
+    assert_eq!(output("1 , 2 , 3 , 2 ndpick # 4 pack $"), "[1, 2, 3, 1]\n");
+    assert_eq!(output("1 , 2 , 3 , 2 ndroll # 3 pack $ depth $"), "[2, 3, 1]\n0\n");
+}
+
+#[test]
+fn stack_primitive_errors() {
+    assert_eq!(error_kind("1 swap"), ErrorKind::StackUnderflow);
+    assert_eq!(error_kind("1 2 rot"), ErrorKind::StackUnderflow);
+    assert_eq!(error_kind("1 1 pick"), ErrorKind::StackUnderflow);
+    assert_eq!(error_kind("1 0 1 - roll"), ErrorKind::OutOfRange);
+    assert_eq!(error_kind("1 , ndswap"), ErrorKind::StackUnderflow);
+    assert_eq!(error_kind("1 , 1 ndpick"), ErrorKind::StackUnderflow);
+    assert_eq!(error_kind("1 , \"a\" ndroll"), ErrorKind::Type);
+}
+
+#[test]
+fn secondary_stack_inspection() {
+    assert_eq!(output("1 , 2 , ndpeek $ nddepth $ nddup nddepth $ # 3 pack $"), "2\n2\n3\n[1, 2, 2]\n");
+    assert_eq!(output("1 , 2 , ndclear nddepth $ depth $"), "0\n0\n");
+    assert_eq!(error_kind("ndpeek"), ErrorKind::StackUnderflow);
+    assert_eq!(error_kind("nddup"), ErrorKind::StackUnderflow);
+}
+
+#[test]
+fn named_stacks() {
+    assert_eq!(output("\"q\" newstack 1 2 \"st\" \"q\" move \"q\" select $ depth $"), "2\n0\n");
+    assert_eq!(output("\"q\" newstack \"q\" select 1 2 \"st\" select depth $ \"q\" select 2 pack $"), "0\n[1, 2]\n");
+    assert_eq!(output("\"q\" newstack \"q\" ndselect 5 , nddepth $ \"nd\" ndselect nddepth $ \"q\" \"st\" move $"), "1\n0\n5\n");
+    assert_eq!(output("\"q\" newstack 1 , \"q\" select # \"nd\" select $"), "1\n");
+}
+
+#[test]
+fn named_stack_errors() {
+    assert_eq!(error_kind("\"missing\" select"), ErrorKind::UnknownStack);
+    assert_eq!(error_kind("\"missing\" ndselect"), ErrorKind::UnknownStack);
+    assert_eq!(error_kind("\"st\" \"missing\" move"), ErrorKind::UnknownStack);
+    assert_eq!(error_kind("\"st\" newstack"), ErrorKind::StackConflict);
+    assert_eq!(error_kind("\"nd\" select"), ErrorKind::StackConflict);
+    assert_eq!(error_kind("\"st\" ndselect"), ErrorKind::StackConflict);
+    assert_eq!(error_kind("\"q\" newstack \"q\" \"st\" move"), ErrorKind::StackUnderflow);
+}
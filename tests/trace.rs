@@ -0,0 +1,49 @@
+mod common;
+
+use common::run_file;
+
+const SOURCE: &str = "{ 1 + } \"inc\" :\n1 inc $";
+
+fn trace(args: &[&str]) -> Vec<String> {
+    let output = run_file(SOURCE, args, "");
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "2\n");
+
+    // drop the name of the temporary source file
+    String::from_utf8(output.stderr).unwrap().lines().map(|line| {
+        line.split_once(" (line ").map_or(line.to_string(), |(_, rest)| format!("(line {}", rest))
+    }).collect()
+}
+
+#[test]
+fn text_trace() {
+    let records = trace(&["--trace"]);
+    assert_eq!(records.len(), 8);
+    assert_eq!(records[0], "(line 1, pos 0) in <main>, depth 0: Code { st=[] nd=[]");
+    assert_eq!(records[5], "(line 1, pos 2) in inc, depth 1: Int 1 st=[1] nd=[]");
+    assert_eq!(records[7], "(line 2, pos 6) in <main>, depth 0: Print $ st=[2] nd=[]");
+}
+
+#[test]
+fn json_trace_with_filters() {
+    let records = run_file(SOURCE, &["--trace=json", "--trace-tops", "--trace-fn=inc"], "").stderr;
+    let records = String::from_utf8(records).unwrap();
+    let records = records.lines().collect::<Vec<&str>>();
+
+    assert_eq!(records.len(), 2);
+    assert!(records[0].starts_with("{\"kind\":\"Int\",\"lexeme\":\"1\",\"source\":"));
+    assert!(records[1].ends_with("\"line\":1,\"pos\":4,\"function\":\"inc\",\"depth\":1,\"st\":[\"1\"],\"nd\":[]}"));
+}
+
+#[test]
+fn line_filter() {
+    let records = trace(&["--trace", "--trace-lines=2"]);
+    assert_eq!(records.len(), 3);
+    assert!(records.iter().all(|record| record.starts_with("(line 2,")));
+}
+
+#[test]
+fn invalid_options() {
+    assert!(!run_file(SOURCE, &["--trace=xml"], "").status.success());
+    assert!(!run_file(SOURCE, &["--trace-lines=a-3"], "").status.success());
+    assert!(!run_file(SOURCE, &["--trace", "--debug"], "").status.success());
+}